@@ -0,0 +1,350 @@
+use crate::Instruction;
+
+pub trait Crane {
+    fn name(&self) -> String;
+    fn run(&self, instruction: &Instruction, stacks: &mut [Vec<char>]);
+    /// Puts the stacks back the way they were before `run` was called with the same instruction
+    fn undo(&self, instruction: &Instruction, stacks: &mut [Vec<char>]);
+}
+
+/// Lifts one crate at a time, so a moved group lands in reverse order
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn run(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        move_crates(stacks, instruction.count, instruction, true);
+    }
+
+    fn undo(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        move_crates(stacks, instruction.count, &instruction.inverted(), true);
+    }
+}
+
+/// Lifts the whole group at once, keeping its order
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn run(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        move_crates(stacks, instruction.count, instruction, false);
+    }
+
+    fn undo(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        move_crates(stacks, instruction.count, &instruction.inverted(), false);
+    }
+}
+
+/// Lifts at most `capacity` crates per trip (unlimited when `None`), optionally
+/// setting each lift down upside-down
+pub struct CustomCrane {
+    pub capacity: Option<usize>,
+    pub reverse: bool,
+}
+
+impl Crane for CustomCrane {
+    fn name(&self) -> String {
+        let capacity = match self.capacity {
+            Some(capacity) => format!("capacity {}", capacity),
+            None => "unlimited capacity".to_owned(),
+        };
+        let order = if self.reverse { ", reversing" } else { "" };
+        format!("Custom crane ({}{})", capacity, order)
+    }
+
+    fn run(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        let capacity = self.capacity(instruction);
+        let mut remaining = instruction.count;
+        while remaining > 0 {
+            let lift = remaining.min(capacity);
+            move_crates(stacks, lift, instruction, self.reverse);
+            remaining -= lift;
+        }
+    }
+
+    fn undo(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        // The partial lift happened last, so it has to come back first
+        let capacity = self.capacity(instruction);
+        let inverted = instruction.inverted();
+        let partial = instruction.count % capacity;
+        if partial > 0 {
+            move_crates(stacks, partial, &inverted, self.reverse);
+        }
+        for _ in 0..instruction.count / capacity {
+            move_crates(stacks, capacity, &inverted, self.reverse);
+        }
+    }
+}
+
+impl CustomCrane {
+    fn capacity(&self, instruction: &Instruction) -> usize {
+        self.capacity.unwrap_or(instruction.count).max(1)
+    }
+}
+
+/// Runs inverted instructions by undoing the original move, so the stacks can be played
+/// backwards through the same validation and tracing as a forward run
+pub struct Inverse<'a>(pub &'a dyn Crane);
+
+impl Crane for Inverse<'_> {
+    fn name(&self) -> String {
+        format!("{} (reversed)", self.0.name())
+    }
+
+    fn run(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        self.0.undo(&instruction.inverted(), stacks);
+    }
+
+    fn undo(&self, instruction: &Instruction, stacks: &mut [Vec<char>]) {
+        self.0.run(&instruction.inverted(), stacks);
+    }
+}
+
+/// Parses a crane from the command line: `9000`, `9001`, or a comma separated list of
+/// `capacity=N` and `reverse`
+pub fn parse_crane(spec: &str) -> Result<Box<dyn Crane>, String> {
+    match spec {
+        "9000" => return Ok(Box::new(CrateMover9000)),
+        "9001" => return Ok(Box::new(CrateMover9001)),
+        _ => (),
+    }
+
+    let mut crane = CustomCrane {
+        capacity: None,
+        reverse: false,
+    };
+    for option in spec.split(',') {
+        match option.split_once('=') {
+            Some(("capacity", value)) => {
+                let capacity = value
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid crane capacity '{}': {}", value, e))?;
+                if capacity == 0 {
+                    return Err("Crane capacity must be at least 1".to_owned());
+                }
+                crane.capacity = Some(capacity);
+            }
+            None if option == "reverse" => crane.reverse = true,
+            _ => return Err(format!("Unknown crane option '{}'", option)),
+        }
+    }
+
+    Ok(Box::new(crane))
+}
+
+fn move_crates(stacks: &mut [Vec<char>], count: usize, instruction: &Instruction, reverse: bool) {
+    // Moving crates onto the stack they came from leaves it unchanged
+    if let Some((source, target)) = pick_pair(stacks, instruction.source, instruction.target) {
+        let start = source.len() - count;
+        if reverse {
+            target.extend(source.drain(start..).rev());
+        } else {
+            target.extend(source.drain(start..));
+        }
+    }
+}
+
+fn pick_pair(
+    stacks: &mut [Vec<char>],
+    source: usize,
+    target: usize,
+) -> Option<(&mut Vec<char>, &mut Vec<char>)> {
+    if source < target {
+        let (low, high) = stacks.split_at_mut(target);
+        Some((&mut low[source], &mut high[0]))
+    } else if target < source {
+        let (low, high) = stacks.split_at_mut(source);
+        Some((&mut high[0], &mut low[target]))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use test_case::test_case;
+
+    use crate::{
+        crane::{parse_crane, Crane, CrateMover9000, CrateMover9001, Inverse},
+        read_instructions, read_stacks, top_crates, Instruction,
+    };
+
+    #[test_case("9000", "CMZ")]
+    #[test_case("9001", "MCD")]
+    #[test_case("capacity=1", "CMZ")]
+    #[test_case("capacity=2", "MCZ")]
+    #[test_case("capacity=2,reverse", "CMZ")]
+    #[test_case("reverse", "CMZ")]
+    fn test_cranes(spec: &str, expected: &str) {
+        let lines = [
+            "    [D]    ".to_owned(),
+            "[N] [C]    ".to_owned(),
+            "[Z] [M] [P]".to_owned(),
+            " 1   2   3 ".to_owned(),
+        ];
+        let instruction_lines = [
+            "move 1 from 2 to 1".to_owned(),
+            "move 3 from 1 to 3".to_owned(),
+            "move 2 from 2 to 1".to_owned(),
+            "move 1 from 1 to 2".to_owned(),
+        ];
+        let mut stacks = read_stacks(&lines);
+        let crane = parse_crane(spec).unwrap();
+
+        for inst in read_instructions(&instruction_lines) {
+            crane.run(&inst, &mut stacks);
+        }
+
+        assert_eq!(top_crates(&stacks), expected);
+    }
+
+    #[test_case("9000")]
+    #[test_case("9001")]
+    #[test_case("capacity=3")]
+    #[test_case("capacity=4,reverse")]
+    #[test_case("reverse")]
+    fn test_undo_restores_stacks(spec: &str) {
+        let crane = parse_crane(spec).unwrap();
+        let initial: Vec<Vec<char>> = (0..5)
+            .map(|s| {
+                (0..20)
+                    .map(|i| (b'A' + ((s * 7 + i) % 26) as u8) as char)
+                    .collect()
+            })
+            .collect();
+        let mut stacks = initial.clone();
+        // Pseudo random moves that never take more crates than a stack holds
+        let mut seed = 17usize;
+        let mut instructions = Vec::new();
+        for _ in 0..200 {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let source = (seed >> 33) % 5;
+            let target = (seed >> 40) % 5;
+            let count = (seed >> 48) % (stacks[source].len() + 1);
+            let instruction = Instruction {
+                count,
+                source,
+                target,
+            };
+            crane.run(&instruction, &mut stacks);
+            instructions.push(instruction);
+        }
+
+        let inverse = Inverse(crane.as_ref());
+        for instruction in instructions.iter().rev() {
+            inverse.run(&instruction.inverted(), &mut stacks);
+        }
+
+        assert_eq!(stacks, initial);
+    }
+
+    #[test_case("capacity=0")]
+    #[test_case("capacity=x")]
+    #[test_case("9002")]
+    fn test_parse_crane_invalid(spec: &str) {
+        assert!(parse_crane(spec).is_err());
+    }
+
+    #[test]
+    fn test_run_same_stack() {
+        let mut stacks = vec![vec!['A', 'B', 'C']];
+        let instruction = Instruction {
+            count: 2,
+            source: 0,
+            target: 0,
+        };
+
+        CrateMover9000.run(&instruction, &mut stacks);
+        CrateMover9001.run(&instruction, &mut stacks);
+
+        assert_eq!(stacks, vec![vec!['A', 'B', 'C']]);
+    }
+
+    // The original crate-at-a-time implementation, kept as a reference for the benchmark
+    fn run_naive(instruction: &Instruction, state: &mut [Vec<char>], move_mul: bool) {
+        let Instruction {
+            count,
+            source,
+            target,
+        } = *instruction;
+        if move_mul {
+            let len = state[source].len();
+            let mut items = state[source][len - count..].to_vec();
+            state[target].append(&mut items);
+            for i in (len - count..len).rev() {
+                state[source].remove(i);
+            }
+        } else {
+            for _ in 0..count {
+                let val = state[source].pop().unwrap();
+                state[target].push(val);
+            }
+        }
+    }
+
+    fn generate_large_input() -> (Vec<Vec<char>>, Vec<Instruction>) {
+        const STACKS: usize = 9;
+        const CRATES_PER_STACK: usize = 1_000_000;
+        let stacks = (0..STACKS)
+            .map(|s| {
+                (0..CRATES_PER_STACK)
+                    .map(|i| (b'A' + ((s + i) % 26) as u8) as char)
+                    .collect()
+            })
+            .collect();
+        let instructions = (0..2_000)
+            .map(|i| {
+                let count = 250_000 + (i / 2 * 7_919) % 250_000;
+                let (source, target) = ((i / 2) % STACKS, (i / 2 + 4) % STACKS);
+                // Every odd step moves the same crates straight back, so no stack runs dry
+                let (source, target) = if i % 2 == 0 {
+                    (source, target)
+                } else {
+                    (target, source)
+                };
+                Instruction {
+                    count,
+                    source,
+                    target,
+                }
+            })
+            .collect();
+
+        (stacks, instructions)
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_run_large() {
+        let cranes: [(&dyn Crane, bool); 2] = [(&CrateMover9000, false), (&CrateMover9001, true)];
+        for (crane, move_mul) in cranes {
+            let (mut stacks, instructions) = generate_large_input();
+            let mut stacks_naive = stacks.clone();
+
+            let start = Instant::now();
+            for inst in &instructions {
+                crane.run(inst, &mut stacks);
+            }
+            let bulk = start.elapsed();
+
+            let start = Instant::now();
+            for inst in &instructions {
+                run_naive(inst, &mut stacks_naive, move_mul);
+            }
+            let naive = start.elapsed();
+
+            println!("{}, bulk: {:?}, naive: {:?}", crane.name(), bulk, naive);
+            assert_eq!(stacks, stacks_naive);
+        }
+    }
+}
@@ -1,57 +1,202 @@
-use std::io::{self, BufRead};
-
-enum Instruction {
-    Move {
-        count: usize,
-        source: usize,
-        target: usize,
-    },
-    MoveMul {
-        count: usize,
-        source: usize,
-        target: usize,
-    },
+use std::{
+    env, fmt,
+    io::{self, BufRead},
+    path::PathBuf,
+    process,
+    time::Duration,
+};
+
+use crane::{parse_crane, Crane, CrateMover9000, CrateMover9001, Inverse};
+use trace::Trace;
+use validate::{validate, Leniency};
+
+mod crane;
+mod trace;
+mod validate;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Instruction {
+    count: usize,
+    source: usize,
+    target: usize,
+}
+
+impl Instruction {
+    fn inverted(&self) -> Self {
+        Self {
+            count: self.count,
+            source: self.target,
+            target: self.source,
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.source.wrapping_add(1),
+            self.target.wrapping_add(1)
+        )
+    }
+}
+
+struct Options {
+    crane: Option<Box<dyn Crane>>,
+    leniency: Leniency,
+    reverse: bool,
+    trace: bool,
+    delay: Duration,
+    trace_json: Option<PathBuf>,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            crane: None,
+            leniency: Leniency::Strict,
+            reverse: false,
+            trace: false,
+            delay: Duration::from_millis(250),
+            trace_json: None,
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--crane" => {
+                    let spec = args.next().expect("--crane requires a crane model");
+                    options.crane = Some(parse_crane(&spec).unwrap());
+                }
+                "--lenient" => {
+                    let mode = args.next().expect("--lenient requires skip or clamp");
+                    options.leniency = match mode.as_str() {
+                        "skip" => Leniency::Skip,
+                        "clamp" => Leniency::Clamp,
+                        _ => panic!("Unknown lenient mode: {}", mode),
+                    };
+                }
+                "--reverse" => options.reverse = true,
+                "--trace" => options.trace = true,
+                "--delay" => {
+                    let millis = args
+                        .next()
+                        .expect("--delay requires a value in milliseconds");
+                    options.delay = Duration::from_millis(millis.parse().unwrap());
+                }
+                "--trace-json" => {
+                    let path = args.next().expect("--trace-json requires a path");
+                    options.trace_json = Some(PathBuf::from(path));
+                }
+                _ => panic!("Unknown argument: {}", arg),
+            }
+        }
+
+        options
+    }
+
+    fn is_recording(&self) -> bool {
+        self.trace || self.trace_json.is_some()
+    }
 }
 
 fn main() {
+    let mut options = Options::from_args(env::args().skip(1));
     let lines: Vec<_> = io::stdin().lock().lines().map(|ln| ln.unwrap()).collect();
     // Split on the \n separating stacks from instructions
     let mut input_parts = lines.split(|ln| ln.is_empty());
     let stack_text = input_parts.next().unwrap();
     let instruction_text = input_parts.next().unwrap();
 
-    // Part 1
-    let mut stacks_1 = read_stacks(stack_text);
-    let instructions = read_instructions(instruction_text, false);
+    // Instructions start after the drawing and the blank separator line
+    let first_instruction_line = stack_text.len() + 2;
+    let mut instructions = (first_instruction_line..)
+        .zip(read_instructions(instruction_text))
+        .collect::<Vec<_>>();
+    // In reverse the drawing is the final arrangement, so undo the moves last to first
+    if options.reverse {
+        instructions = instructions
+            .into_iter()
+            .rev()
+            .map(|(line, inst)| (line, inst.inverted()))
+            .collect();
+    }
+    let runs: Vec<(String, Box<dyn Crane>)> = match options.crane.take() {
+        Some(crane) => Vec::from([(crane.name(), crane)]),
+        None => Vec::from([
+            (
+                "Part 1".to_owned(),
+                Box::new(CrateMover9000) as Box<dyn Crane>,
+            ),
+            ("Part 2".to_owned(), Box::new(CrateMover9001)),
+        ]),
+    };
+
+    let mut results = Vec::new();
+    let mut traces = Vec::new();
+    for (label, crane) in &runs {
+        let inverse;
+        let crane = if options.reverse {
+            inverse = Inverse(crane.as_ref());
+            &inverse as &dyn Crane
+        } else {
+            crane.as_ref()
+        };
+        let initial = read_stacks(stack_text);
+        let validated = match validate(&instructions, &initial, crane, options.leniency) {
+            Ok(validated) => validated,
+            Err(invalid) => {
+                eprintln!("{}: invalid move on {}", label, invalid);
+                process::exit(1);
+            }
+        };
+        for problem in &validated.problems {
+            eprintln!("{}: ignoring invalid move on {}", label, problem);
+        }
 
-    instructions
-        .iter()
-        .for_each(|inst| run_instruction(inst, &mut stacks_1));
+        // Validation has already made the moves, so they only need making again to record them
+        let stacks = if options.is_recording() {
+            let mut stacks = initial;
+            let trace = Trace::record(&validated.instructions, &mut stacks, crane);
+            traces.push((label, trace));
+            stacks
+        } else {
+            validated.stacks
+        };
+        if options.reverse {
+            results.push(format!("{}:\n{}", label, render_stacks(&stacks)));
+        } else {
+            results.push(format!("{}: {}", label, top_crates(&stacks)));
+        }
+    }
 
-    let part_1: String = String::from_iter(
-        stacks_1
+    if options.trace {
+        let mut stdout = io::stdout().lock();
+        for (label, trace) in &traces {
+            trace.replay(&mut stdout, label, options.delay).unwrap();
+        }
+    }
+    if let Some(path) = &options.trace_json {
+        let runs = traces
             .iter()
-            .map(|stack| stack.last().unwrap())
-            .collect::<Vec<_>>(),
-    );
+            .map(|(label, trace)| format!("\"{}\":{}", label, trace.to_json()))
+            .collect::<Vec<_>>()
+            .join(",");
+        std::fs::write(path, format!("{{{}}}", runs)).unwrap();
+    }
 
-    // Part 2
-    let mut stacks_2 = read_stacks(stack_text);
-    let instructions_move_mul = read_instructions(instruction_text, true);
+    for result in results {
+        println!("{}", result);
+    }
+}
 
-    instructions_move_mul
+// An empty stack shows as a space, so every letter stays under the stack it came from
+fn top_crates(stacks: &[Vec<char>]) -> String {
+    stacks
         .iter()
-        .for_each(|inst| run_instruction(inst, &mut stacks_2));
-
-    let part_2: String = String::from_iter(
-        stacks_2
-            .iter()
-            .map(|stack| stack.last().unwrap())
-            .collect::<Vec<_>>(),
-    );
-
-    println!("Part 1: {}", part_1);
-    println!("Part 2: {}", part_2);
+        .map(|stack| stack.last().copied().unwrap_or(' '))
+        .collect()
 }
 
 fn read_stacks(lines: &[String]) -> Vec<Vec<char>> {
@@ -78,60 +223,52 @@ fn read_stacks(lines: &[String]) -> Vec<Vec<char>> {
     stacks
 }
 
-fn read_instructions(lines: &[String], move_mul: bool) -> Vec<Instruction> {
-    lines
-        .iter()
-        .map(|ln| parse_instruction(ln, move_mul))
-        .collect()
+fn render_stacks(stacks: &[Vec<char>]) -> String {
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut lines = Vec::with_capacity(height + 1);
+    for level in (0..height).rev() {
+        let line = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(item) => format!("[{}]", item),
+                None => "   ".to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(line);
+    }
+    let labels = (1..=stacks.len())
+        .map(|number| format!(" {} ", number))
+        .collect::<Vec<_>>()
+        .join(" ");
+    lines.push(labels);
+
+    lines.join("\n")
+}
+
+fn read_instructions(lines: &[String]) -> Vec<Instruction> {
+    lines.iter().map(|ln| parse_instruction(ln)).collect()
 }
 
-fn parse_instruction(line: &str, move_mul: bool) -> Instruction {
+fn parse_instruction(line: &str) -> Instruction {
     let split_line = line.split(' ').collect::<Vec<_>>();
+    // Stack 0 wraps to an out of range index, which the validator then reports
     match split_line[0] {
-        "move" if move_mul => Instruction::MoveMul {
-            count: split_line[1].parse().unwrap(),
-            source: split_line[3].parse::<usize>().unwrap() - 1,
-            target: split_line[5].parse::<usize>().unwrap() - 1,
-        },
-        "move" => Instruction::Move {
+        "move" => Instruction {
             count: split_line[1].parse().unwrap(),
-            source: split_line[3].parse::<usize>().unwrap() - 1,
-            target: split_line[5].parse::<usize>().unwrap() - 1,
+            source: split_line[3].parse::<usize>().unwrap().wrapping_sub(1),
+            target: split_line[5].parse::<usize>().unwrap().wrapping_sub(1),
         },
         _ => unimplemented!(),
     }
 }
 
-fn run_instruction(instruction: &Instruction, state: &mut [Vec<char>]) {
-    match instruction {
-        Instruction::Move {
-            count,
-            source,
-            target,
-        } => {
-            for _ in 0..*count {
-                let val = state[*source].pop().unwrap();
-                state[*target].push(val);
-            }
-        }
-        Instruction::MoveMul {
-            count,
-            source,
-            target,
-        } => {
-            let len = state[*source].len();
-            let mut items = state[*source][len - *count..].to_vec();
-            state[*target].append(&mut items);
-            for i in (len - *count..len).rev() {
-                state[*source].remove(i);
-            }
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
-    use crate::{read_instructions, read_stacks, run_instruction, Instruction};
+    use crate::{
+        crane::{Crane, CrateMover9000, CrateMover9001},
+        read_instructions, read_stacks, render_stacks, top_crates, Instruction,
+    };
 
     #[test]
     fn test_read_stacks() {
@@ -159,6 +296,20 @@ mod test {
         assert_eq!('P', stacks[2][0]);
     }
 
+    #[test]
+    fn test_render_stacks() {
+        let lines = [
+            "    [D]    ".to_owned(),
+            "[N] [C]    ".to_owned(),
+            "[Z] [M] [P]".to_owned(),
+            " 1   2   3 ".to_owned(),
+        ];
+
+        let stacks = read_stacks(&lines);
+
+        assert_eq!(render_stacks(&stacks), lines.join("\n"));
+    }
+
     #[test]
     fn test_read_instructions() {
         let lines = [
@@ -168,7 +319,7 @@ mod test {
             "move 1 from 1 to 2".to_owned(),
         ];
 
-        let instructions = read_instructions(&lines, false);
+        let instructions = read_instructions(&lines);
 
         assert_eq!(4, instructions.len());
 
@@ -196,21 +347,13 @@ mod test {
             "move 1 from 1 to 2".to_owned(),
         ];
 
-        let instructions = read_instructions(&instruction_lines, false);
+        let instructions = read_instructions(&instruction_lines);
 
         for inst in instructions {
-            run_instruction(&inst, &mut stacks);
-            println!("{:?}", stacks);
+            CrateMover9000.run(&inst, &mut stacks);
         }
 
-        let part_1: String = String::from_iter(
-            stacks
-                .iter()
-                .map(|stack| stack.last().unwrap())
-                .collect::<Vec<_>>(),
-        );
-
-        assert_eq!("CMZ", part_1.as_str())
+        assert_eq!("CMZ", top_crates(&stacks))
     }
 
     #[test]
@@ -231,21 +374,13 @@ mod test {
             "move 1 from 1 to 2".to_owned(),
         ];
 
-        let instructions = read_instructions(&instruction_lines, true);
+        let instructions = read_instructions(&instruction_lines);
 
         for inst in instructions {
-            run_instruction(&inst, &mut stacks);
-            println!("{:?}", stacks);
+            CrateMover9001.run(&inst, &mut stacks);
         }
 
-        let part_2: String = String::from_iter(
-            stacks
-                .iter()
-                .map(|stack| stack.last().unwrap())
-                .collect::<Vec<_>>(),
-        );
-
-        assert_eq!("MCD", part_2.as_str())
+        assert_eq!("MCD", top_crates(&stacks))
     }
 
     fn assert_move_instruction(
@@ -254,25 +389,8 @@ mod test {
         expected_source: usize,
         expected_target: usize,
     ) {
-        match instruction {
-            Instruction::Move {
-                count,
-                source,
-                target,
-            } => {
-                assert_eq!(*count, expected_count);
-                assert_eq!(*source, expected_source);
-                assert_eq!(*target, expected_target);
-            }
-            Instruction::MoveMul {
-                count,
-                source,
-                target,
-            } => {
-                assert_eq!(*count, expected_count);
-                assert_eq!(*source, expected_source);
-                assert_eq!(*target, expected_target);
-            }
-        };
+        assert_eq!(instruction.count, expected_count);
+        assert_eq!(instruction.source, expected_source);
+        assert_eq!(instruction.target, expected_target);
     }
 }
@@ -0,0 +1,195 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use crate::{crane::Crane, render_stacks, Instruction};
+
+pub struct TraceStep {
+    pub instruction: Instruction,
+    pub before: Vec<Vec<char>>,
+    pub after: Vec<Vec<char>>,
+}
+
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    pub fn record(
+        instructions: &[Instruction],
+        stacks: &mut [Vec<char>],
+        crane: &dyn Crane,
+    ) -> Self {
+        let mut steps = Vec::with_capacity(instructions.len());
+        for instruction in instructions {
+            let before = stacks.to_vec();
+            crane.run(instruction, stacks);
+            steps.push(TraceStep {
+                instruction: *instruction,
+                before,
+                after: stacks.to_vec(),
+            });
+        }
+
+        Self { steps }
+    }
+
+    pub fn replay(&self, out: &mut impl Write, title: &str, delay: Duration) -> io::Result<()> {
+        let animate = !delay.is_zero();
+        let total = self.steps.len();
+        for (index, step) in self.steps.iter().enumerate() {
+            if animate {
+                // Clear the screen and move the cursor home so each frame redraws in place
+                write!(out, "\x1b[2J\x1b[H")?;
+            }
+            writeln!(
+                out,
+                "{} - step {}/{}: {}",
+                title,
+                index + 1,
+                total,
+                step.instruction
+            )?;
+            writeln!(out, "{}\n", render_stacks(&step.after))?;
+            out.flush()?;
+            if animate {
+                thread::sleep(delay);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> String {
+        let steps = self
+            .steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                format!(
+                    "{{\"step\":{},\"instruction\":\"{}\",\"count\":{},\"source\":{},\"target\":{},\"before\":{},\"after\":{}}}",
+                    index + 1,
+                    step.instruction,
+                    step.instruction.count,
+                    step.instruction.source + 1,
+                    step.instruction.target + 1,
+                    stacks_to_json(&step.before),
+                    stacks_to_json(&step.after)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!("[{}]", steps)
+    }
+}
+
+// Each stack is written bottom to top as a single string, e.g. ["ZN","MCD","P"]
+fn stacks_to_json(stacks: &[Vec<char>]) -> String {
+    let stacks = stacks
+        .iter()
+        .map(|stack| {
+            let mut items = String::new();
+            stack
+                .iter()
+                .for_each(|item| push_json_char(&mut items, *item));
+            format!("\"{}\"", items)
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    format!("[{}]", stacks)
+}
+
+// JSON strings only need quotes, backslashes and control characters escaping, anything
+// else is written as it is
+fn push_json_char(out: &mut String, item: char) {
+    match item {
+        '"' => out.push_str("\\\""),
+        '\\' => out.push_str("\\\\"),
+        item if (item as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", item as u32)),
+        item => out.push(item),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        crane::CrateMover9000,
+        read_instructions, read_stacks,
+        trace::{stacks_to_json, Trace},
+    };
+
+    fn example() -> (Vec<Vec<char>>, Vec<crate::Instruction>) {
+        let lines = [
+            "    [D]    ".to_owned(),
+            "[N] [C]    ".to_owned(),
+            "[Z] [M] [P]".to_owned(),
+            " 1   2   3 ".to_owned(),
+        ];
+        let instruction_lines = [
+            "move 1 from 2 to 1".to_owned(),
+            "move 3 from 1 to 3".to_owned(),
+            "move 2 from 2 to 1".to_owned(),
+            "move 1 from 1 to 2".to_owned(),
+        ];
+
+        (read_stacks(&lines), read_instructions(&instruction_lines))
+    }
+
+    #[test]
+    fn test_record() {
+        let (mut stacks, instructions) = example();
+
+        let trace = Trace::record(&instructions, &mut stacks, &CrateMover9000);
+
+        assert_eq!(trace.steps.len(), 4);
+        assert_eq!(
+            trace.steps[0].before,
+            vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+        );
+        assert_eq!(
+            trace.steps[0].after,
+            vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]
+        );
+        assert_eq!(trace.steps[3].after, stacks);
+        for pair in trace.steps.windows(2) {
+            assert_eq!(pair[0].after, pair[1].before);
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let (mut stacks, instructions) = example();
+
+        let trace = Trace::record(&instructions[..1], &mut stacks, &CrateMover9000);
+
+        assert_eq!(
+            trace.to_json(),
+            "[{\"step\":1,\"instruction\":\"move 1 from 2 to 1\",\"count\":1,\"source\":2,\"target\":1,\"before\":[\"ZN\",\"MCD\",\"P\"],\"after\":[\"ZND\",\"MC\",\"P\"]}]"
+        );
+    }
+
+    #[test]
+    fn test_stacks_to_json_escapes() {
+        let stacks = vec![vec!['É', 'ß'], vec!['"', '\\', '\n']];
+
+        assert_eq!(stacks_to_json(&stacks), "[\"Éß\",\"\\\"\\\\\\u000a\"]");
+    }
+
+    #[test]
+    fn test_replay_without_delay() {
+        let (mut stacks, instructions) = example();
+        let trace = Trace::record(&instructions[..1], &mut stacks, &CrateMover9000);
+        let mut out = Vec::new();
+
+        trace.replay(&mut out, "Part 1", Duration::ZERO).unwrap();
+
+        let expected = "Part 1 - step 1/1: move 1 from 2 to 1\n[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+
+use crate::{crane::Crane, render_stacks, Instruction};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Leniency {
+    Strict,
+    Skip,
+    Clamp,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    UnknownStack(usize),
+    NotEnoughCrates { stack: usize, available: usize },
+}
+
+#[derive(Debug)]
+pub struct InvalidMove {
+    pub line: usize,
+    pub instruction: Instruction,
+    pub stacks: Vec<Vec<char>>,
+    pub problem: Problem,
+}
+
+impl fmt::Display for InvalidMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.problem {
+            Problem::UnknownStack(stack) => format!(
+                "stack {} does not exist, there are {} stacks",
+                stack,
+                self.stacks.len()
+            ),
+            Problem::NotEnoughCrates { stack, available } => format!(
+                "stack {} only holds {} crates, cannot move {}",
+                stack, available, self.instruction.count
+            ),
+        };
+        write!(
+            f,
+            "line {}: {}: {}\n{}",
+            self.line,
+            self.instruction,
+            problem,
+            render_stacks(&self.stacks)
+        )
+    }
+}
+
+pub struct Validated {
+    pub instructions: Vec<Instruction>,
+    pub problems: Vec<InvalidMove>,
+    /// The stacks once every valid move has been made
+    pub stacks: Vec<Vec<char>>,
+}
+
+/// Simulates the line numbered instructions against a copy of the stacks, failing on the
+/// first move that cannot be made. Lenient modes drop or shrink those moves instead and
+/// report them
+pub fn validate(
+    instructions: &[(usize, Instruction)],
+    stacks: &[Vec<char>],
+    crane: &dyn Crane,
+    leniency: Leniency,
+) -> Result<Validated, InvalidMove> {
+    let mut state = stacks.to_vec();
+    let mut valid = Vec::with_capacity(instructions.len());
+    let mut problems = Vec::new();
+
+    for (line, instruction) in instructions {
+        let problem = match check(instruction, &state) {
+            Some(problem) => problem,
+            None => {
+                crane.run(instruction, &mut state);
+                valid.push(*instruction);
+                continue;
+            }
+        };
+
+        let clamped = match (&problem, leniency) {
+            (Problem::NotEnoughCrates { available, .. }, Leniency::Clamp) if *available > 0 => {
+                Some(Instruction {
+                    count: *available,
+                    ..*instruction
+                })
+            }
+            _ => None,
+        };
+        let invalid = InvalidMove {
+            line: *line,
+            instruction: *instruction,
+            stacks: state.clone(),
+            problem,
+        };
+        if leniency == Leniency::Strict {
+            return Err(invalid);
+        }
+        problems.push(invalid);
+
+        if let Some(clamped) = clamped {
+            crane.run(&clamped, &mut state);
+            valid.push(clamped);
+        }
+    }
+
+    Ok(Validated {
+        instructions: valid,
+        problems,
+        stacks: state,
+    })
+}
+
+fn check(instruction: &Instruction, stacks: &[Vec<char>]) -> Option<Problem> {
+    for stack in [instruction.source, instruction.target] {
+        if stack >= stacks.len() {
+            return Some(Problem::UnknownStack(stack.wrapping_add(1)));
+        }
+    }
+
+    let available = stacks[instruction.source].len();
+    if instruction.count > available {
+        return Some(Problem::NotEnoughCrates {
+            stack: instruction.source + 1,
+            available,
+        });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::{
+        crane::{Crane, CrateMover9001},
+        read_instructions, read_stacks, top_crates,
+        validate::{validate, Leniency, Problem},
+    };
+
+    fn example_stacks() -> Vec<Vec<char>> {
+        let lines = [
+            "    [D]    ".to_owned(),
+            "[N] [C]    ".to_owned(),
+            "[Z] [M] [P]".to_owned(),
+            " 1   2   3 ".to_owned(),
+        ];
+
+        read_stacks(&lines)
+    }
+
+    #[test_case("move 4 from 1 to 2", Problem::NotEnoughCrates { stack: 1, available: 3 })]
+    #[test_case("move 1 from 0 to 2", Problem::UnknownStack(0))]
+    #[test_case("move 1 from 1 to 4", Problem::UnknownStack(4))]
+    fn test_validate_strict(bad_line: &str, expected: Problem) {
+        let lines = [
+            "move 1 from 2 to 1".to_owned(),
+            bad_line.to_owned(),
+            "move 1 from 1 to 2".to_owned(),
+        ];
+        let instructions = (6..).zip(read_instructions(&lines)).collect::<Vec<_>>();
+
+        let result = validate(
+            &instructions,
+            &example_stacks(),
+            &CrateMover9001,
+            Leniency::Strict,
+        );
+        let Err(invalid) = result else {
+            panic!("expected an invalid move");
+        };
+
+        assert_eq!(invalid.line, 7);
+        assert_eq!(invalid.problem, expected);
+        assert_eq!(
+            invalid.stacks,
+            vec![vec!['Z', 'N', 'D'], vec!['M', 'C'], vec!['P']]
+        );
+    }
+
+    #[test_case(Leniency::Skip, 2, " ZN")]
+    #[test_case(Leniency::Clamp, 3, "MCD")]
+    fn test_validate_lenient(leniency: Leniency, expected_len: usize, expected_top: &str) {
+        let lines = [
+            "move 5 from 2 to 1".to_owned(),
+            "move 1 from 3 to 9".to_owned(),
+            "move 1 from 1 to 3".to_owned(),
+            "move 1 from 1 to 2".to_owned(),
+        ];
+        let instructions = (6..).zip(read_instructions(&lines)).collect::<Vec<_>>();
+        let mut stacks = example_stacks();
+
+        let validated = validate(&instructions, &stacks, &CrateMover9001, leniency).unwrap();
+        for inst in &validated.instructions {
+            CrateMover9001.run(inst, &mut stacks);
+        }
+
+        assert_eq!(validated.problems.len(), 2);
+        assert_eq!(validated.instructions.len(), expected_len);
+        assert_eq!(validated.stacks, stacks);
+        assert_eq!(top_crates(&validated.stacks), expected_top);
+    }
+}
@@ -1,48 +1,117 @@
 use std::{
-    collections::HashSet,
+    env,
     io::{self, Read},
 };
 
-fn main() {
-    let mut input_buf = Vec::new();
-    let _: usize = io::stdin().lock().read_to_end(&mut input_buf).unwrap();
+use packets::{hex_dump, text_dump, Packets};
+use scanner::{DeviceScanner, MarkerKind, MESSAGE_MARKER_SIZE, PACKET_MARKER_SIZE};
 
-    let part_1 = find_message_start(&input_buf, 4);
-    let part_2 = find_message_start(&input_buf, 14);
-    println!("Part 1: {}", part_1);
-    println!("Part 2: {}", part_2);
+mod packets;
+mod scanner;
+
+enum DumpFormat {
+    Hex,
+    Text,
 }
 
-fn find_message_start(input: &[u8], marker_size: usize) -> usize {
-    let mut window_start = 0usize;
-    let mut message_start = 0usize;
-
-    while message_start == 0usize {
-        let mut curr_items = HashSet::new();
-        for (index, item) in input[window_start..window_start + marker_size]
-            .iter()
-            .rev()
-            .enumerate()
-        {
-            if !&curr_items.insert(*item) {
-                window_start = window_start + marker_size - index;
-                let _ = &curr_items.clear();
-                break;
+fn main() {
+    let mut report_all = false;
+    let mut framing = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let marker_size = match arg.as_str() {
+            "--all" => {
+                report_all = true;
+                continue;
             }
-        }
+            // Packets split at every start-of-packet marker, which in a real stream leaves
+            // most of them empty, while `--messages` frames the data between the far
+            // rarer start-of-message markers
+            "--packets" => PACKET_MARKER_SIZE,
+            "--messages" => MESSAGE_MARKER_SIZE,
+            _ => panic!("Unknown argument: {}", arg),
+        };
+        let format = match args.next().as_deref() {
+            Some("hex") => DumpFormat::Hex,
+            Some("text") => DumpFormat::Text,
+            _ => panic!("{} requires hex or text", arg),
+        };
+        framing = Some((marker_size, format));
+    }
+
+    if let Some((marker_size, format)) = framing {
+        dump_packets(marker_size, format);
+        return;
+    }
 
-        if curr_items.len() != marker_size {
-            continue;
+    let mut scanner = DeviceScanner::new(report_all);
+    let mut packet_start = None;
+    let mut message_start = None;
+
+    // Markers are reported while stdin is still being read, and reading stops once the
+    // first of each has been found
+    scanner
+        .scan_line(io::stdin().lock(), |event| {
+            if report_all {
+                let kind = match event.kind {
+                    MarkerKind::StartOfPacket => "start-of-packet",
+                    MarkerKind::StartOfMessage => "start-of-message",
+                };
+                println!("{} marker at {}", kind, event.position);
+            }
+            match event.kind {
+                MarkerKind::StartOfPacket => packet_start = packet_start.or(Some(event.position)),
+                MarkerKind::StartOfMessage => {
+                    message_start = message_start.or(Some(event.position))
+                }
+            }
+        })
+        .unwrap();
+
+    println!("Part 1: {}", describe_marker(packet_start));
+    println!("Part 2: {}", describe_marker(message_start));
+}
+
+fn dump_packets(marker_size: usize, format: DumpFormat) {
+    let mut input_buf = Vec::new();
+    let _: usize = io::stdin().lock().read_to_end(&mut input_buf).unwrap();
+    // The trailing newline is not part of the datastream
+    let input = input_buf.trim_ascii_end();
+
+    for (index, packet) in Packets::new(input, marker_size).enumerate() {
+        println!(
+            "Packet {} at {} (marker at {}), {} bytes",
+            index + 1,
+            packet.offset,
+            packet.marker_offset,
+            packet.data.len()
+        );
+        match format {
+            DumpFormat::Hex => print!("{}", hex_dump(packet.data, packet.offset)),
+            DumpFormat::Text => println!("{}", text_dump(packet.data)),
         }
+    }
+}
 
-        message_start = window_start + marker_size
+fn describe_marker(message_start: Option<usize>) -> String {
+    match message_start {
+        Some(message_start) => message_start.to_string(),
+        None => "no marker found".to_owned(),
     }
+}
 
-    message_start
+fn find_message_start(input: &[u8], marker_size: usize) -> Option<usize> {
+    let mut scanner = scanner::MarkerScanner::new(marker_size);
+    input
+        .iter()
+        .position(|item| scanner.push(*item))
+        .map(|index| index + 1)
 }
 
 #[cfg(test)]
 mod test {
+    use std::{collections::HashSet, time::Instant};
+
     use test_case::test_case;
 
     use crate::find_message_start;
@@ -59,6 +128,82 @@ mod test {
     fn test_find_message_start(buffer: &[u8], marker_size: usize, expected_start: usize) {
         let actual_start = find_message_start(buffer, marker_size);
 
-        assert_eq!(actual_start, expected_start);
+        assert_eq!(actual_start, Some(expected_start));
+    }
+
+    #[test_case(b"", 4)]
+    #[test_case(b"abc", 4)]
+    #[test_case(b"abcabcabcabcabc", 4)]
+    #[test_case(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", 27)]
+    fn test_find_message_start_no_marker(buffer: &[u8], marker_size: usize) {
+        assert_eq!(find_message_start(buffer, marker_size), None);
+    }
+
+    // The original window rescanning implementation, kept as a reference for the benchmark
+    fn find_message_start_rescan(input: &[u8], marker_size: usize) -> usize {
+        let mut window_start = 0usize;
+        let mut message_start = 0usize;
+
+        while message_start == 0usize {
+            let mut curr_items = HashSet::new();
+            for (index, item) in input[window_start..window_start + marker_size]
+                .iter()
+                .rev()
+                .enumerate()
+            {
+                if !&curr_items.insert(*item) {
+                    window_start = window_start + marker_size - index;
+                    let _ = &curr_items.clear();
+                    break;
+                }
+            }
+
+            if curr_items.len() != marker_size {
+                continue;
+            }
+
+            message_start = window_start + marker_size
+        }
+
+        message_start
+    }
+
+    // A stream drawn from marker_size - 1 letters can only contain a marker overlapping
+    // the distinct tail appended to it
+    fn generate_stream(len: usize, marker_size: usize) -> Vec<u8> {
+        let mut seed = 0x2545f4914f6cdd1du64;
+        let mut stream = (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                b'a' + (seed % (marker_size as u64 - 1)) as u8
+            })
+            .collect::<Vec<_>>();
+        stream.extend((0..marker_size as u8).map(|i| b'z' - i));
+        stream
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_find_message_start() {
+        for marker_size in [4, 14, 26] {
+            let stream = generate_stream(8 * 1024 * 1024, marker_size);
+
+            let start = Instant::now();
+            let linear = find_message_start(&stream, marker_size);
+            let linear_time = start.elapsed();
+
+            let start = Instant::now();
+            let rescan = find_message_start_rescan(&stream, marker_size);
+            let rescan_time = start.elapsed();
+
+            println!(
+                "marker size {}: linear {:?}, rescan {:?}",
+                marker_size, linear_time, rescan_time
+            );
+            assert!(linear.unwrap() > stream.len() - marker_size);
+            assert_eq!(linear, Some(rescan));
+        }
     }
 }
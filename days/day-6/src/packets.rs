@@ -0,0 +1,151 @@
+use std::fmt::Write;
+
+use crate::find_message_start;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet<'a> {
+    /// Offset of the first byte of the marker that introduces the packet
+    pub marker_offset: usize,
+    /// Offset of the first byte after the marker
+    pub offset: usize,
+    pub data: &'a [u8],
+}
+
+/// Splits a datastream into the runs of bytes following each marker. A packet ends where
+/// the next marker begins, or at the end of the stream. The search for that marker starts
+/// afresh after the last one, so once the bytes after a marker are all different the next
+/// marker starts straight away and the packet is empty. Short markers turn up almost
+/// everywhere in a real stream, leaving most of its packets empty, while the longer
+/// start-of-message markers are rare enough to frame data
+pub struct Packets<'a> {
+    input: &'a [u8],
+    marker_size: usize,
+    position: usize,
+}
+
+impl<'a> Packets<'a> {
+    pub fn new(input: &'a [u8], marker_size: usize) -> Self {
+        Self {
+            input,
+            marker_size,
+            position: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Packets<'a> {
+    type Item = Packet<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset =
+            self.position + find_message_start(&self.input[self.position..], self.marker_size)?;
+        let end = match find_message_start(&self.input[offset..], self.marker_size) {
+            Some(next_marker_end) => offset + next_marker_end - self.marker_size,
+            None => self.input.len(),
+        };
+        self.position = end;
+
+        Some(Packet {
+            marker_offset: offset - self.marker_size,
+            offset,
+            data: &self.input[offset..end],
+        })
+    }
+}
+
+/// Formats bytes 16 to a line with their stream offset, hex values and printable text
+pub fn hex_dump(data: &[u8], offset: usize) -> String {
+    let mut dump = String::new();
+    for (index, line) in data.chunks(16).enumerate() {
+        let hex = line
+            .iter()
+            .map(|item| format!("{:02x}", item))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = line
+            .iter()
+            .map(|item| match item {
+                0x20..=0x7e => *item as char,
+                _ => '.',
+            })
+            .collect::<String>();
+        writeln!(dump, "{:08x}  {:<47}  |{}|", offset + index * 16, hex, text).unwrap();
+    }
+
+    dump
+}
+
+/// Formats bytes as text, escaping anything that is not printable ASCII
+pub fn text_dump(data: &[u8]) -> String {
+    data.escape_ascii().to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::packets::{hex_dump, text_dump, Packet, Packets};
+
+    #[test]
+    fn test_packets() {
+        let input = b"abcdxxxyzwqqqrst";
+
+        let packets = Packets::new(input, 4).collect::<Vec<_>>();
+
+        assert_eq!(
+            packets,
+            vec![
+                Packet {
+                    marker_offset: 0,
+                    offset: 4,
+                    data: b"xx",
+                },
+                Packet {
+                    marker_offset: 6,
+                    offset: 10,
+                    data: b"qq",
+                },
+                Packet {
+                    marker_offset: 12,
+                    offset: 16,
+                    data: b"",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_packets_back_to_back() {
+        let packets = Packets::new(b"abcdefgh", 4).collect::<Vec<_>>();
+
+        assert_eq!(
+            packets,
+            vec![
+                Packet {
+                    marker_offset: 0,
+                    offset: 4,
+                    data: b"",
+                },
+                Packet {
+                    marker_offset: 4,
+                    offset: 8,
+                    data: b"",
+                },
+            ]
+        );
+        // The same bytes frame a single packet with a longer marker
+        assert_eq!(Packets::new(b"abcdefghaab", 8).count(), 1);
+    }
+
+    #[test]
+    fn test_packets_without_marker() {
+        assert_eq!(Packets::new(b"abcabc", 4).count(), 0);
+    }
+
+    #[test]
+    fn test_dumps() {
+        assert_eq!(
+            hex_dump(b"hello\n", 32),
+            "00000020  68 65 6c 6c 6f 0a                                |hello.|\n"
+        );
+        assert_eq!(text_dump(b"hi\n\x00"), "hi\\n\\x00");
+    }
+}
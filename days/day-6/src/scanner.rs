@@ -0,0 +1,198 @@
+use std::io::{self, BufRead};
+
+pub const PACKET_MARKER_SIZE: usize = 4;
+pub const MESSAGE_MARKER_SIZE: usize = 14;
+
+pub struct MarkerScanner {
+    marker_size: usize,
+    // Stream position just past the last time each byte value was seen, 0 if never seen
+    seen_until: [usize; 256],
+    window_start: usize,
+    position: usize,
+}
+
+impl MarkerScanner {
+    pub fn new(marker_size: usize) -> Self {
+        Self {
+            marker_size,
+            seen_until: [0; 256],
+            window_start: 0,
+            position: 0,
+        }
+    }
+
+    /// Number of bytes pushed so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Returns true when the last `marker_size` bytes, ending with this one, are all different
+    pub fn push(&mut self, item: u8) -> bool {
+        // A repeat inside the window means no marker can start at or before it
+        self.window_start = self.window_start.max(self.seen_until[item as usize]);
+        self.position += 1;
+        self.seen_until[item as usize] = self.position;
+
+        self.position - self.window_start >= self.marker_size
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    StartOfPacket,
+    StartOfMessage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarkerEvent {
+    pub kind: MarkerKind,
+    pub position: usize,
+}
+
+/// Watches a datastream for both marker kinds. Unless `report_all` is set only the first
+/// marker of each kind is reported, and the scanner is done once both have been seen
+pub struct DeviceScanner {
+    packet: MarkerScanner,
+    message: MarkerScanner,
+    report_all: bool,
+    found_packet: bool,
+    found_message: bool,
+}
+
+impl DeviceScanner {
+    pub fn new(report_all: bool) -> Self {
+        Self {
+            packet: MarkerScanner::new(PACKET_MARKER_SIZE),
+            message: MarkerScanner::new(MESSAGE_MARKER_SIZE),
+            report_all,
+            found_packet: false,
+            found_message: false,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        !self.report_all && self.found_packet && self.found_message
+    }
+
+    pub fn feed(&mut self, chunk: &[u8], mut on_event: impl FnMut(MarkerEvent)) {
+        for item in chunk {
+            if self.is_done() {
+                return;
+            }
+            if self.packet.push(*item) && (self.report_all || !self.found_packet) {
+                self.found_packet = true;
+                on_event(MarkerEvent {
+                    kind: MarkerKind::StartOfPacket,
+                    position: self.packet.position(),
+                });
+            }
+            if self.message.push(*item) && (self.report_all || !self.found_message) {
+                self.found_message = true;
+                on_event(MarkerEvent {
+                    kind: MarkerKind::StartOfMessage,
+                    position: self.message.position(),
+                });
+            }
+        }
+    }
+
+    /// Feeds the reader through the scanner a buffer at a time, stopping at the end of the
+    /// line, the end of the input, or as soon as the scanner is done
+    pub fn scan_line(
+        &mut self,
+        mut reader: impl BufRead,
+        mut on_event: impl FnMut(MarkerEvent),
+    ) -> io::Result<()> {
+        while !self.is_done() {
+            let chunk = reader.fill_buf()?;
+            if chunk.is_empty() {
+                break;
+            }
+            let line_end = chunk.iter().position(|item| *item == b'\n');
+            let data = match line_end {
+                Some(line_end) => &chunk[..line_end],
+                None => chunk,
+            };
+            self.feed(data, &mut on_event);
+            let consumed = chunk.len();
+            reader.consume(consumed);
+            if line_end.is_some() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use test_case::test_case;
+
+    use crate::scanner::{DeviceScanner, MarkerEvent, MarkerKind};
+
+    fn collect_events(input: &[u8], chunk_size: usize, report_all: bool) -> Vec<MarkerEvent> {
+        let mut scanner = DeviceScanner::new(report_all);
+        let mut events = Vec::new();
+        for chunk in input.chunks(chunk_size) {
+            scanner.feed(chunk, |event| events.push(event));
+        }
+        events
+    }
+
+    #[test_case(1)]
+    #[test_case(3)]
+    #[test_case(64)]
+    fn test_feed_in_chunks(chunk_size: usize) {
+        let events = collect_events(b"mjqjpqmgbljsphdztnvjfqwrcgsmlb", chunk_size, false);
+
+        assert_eq!(
+            events,
+            vec![
+                MarkerEvent {
+                    kind: MarkerKind::StartOfPacket,
+                    position: 7
+                },
+                MarkerEvent {
+                    kind: MarkerKind::StartOfMessage,
+                    position: 19
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_feed_report_all() {
+        let events = collect_events(b"aabcdeeabcd", 4, true);
+
+        let packets = events
+            .iter()
+            .filter(|event| event.kind == MarkerKind::StartOfPacket)
+            .map(|event| event.position)
+            .collect::<Vec<_>>();
+
+        assert_eq!(packets, vec![5, 6, 10, 11]);
+        assert!(events.iter().all(|e| e.kind == MarkerKind::StartOfPacket));
+    }
+
+    #[test]
+    fn test_scan_line_stops_at_newline() {
+        let mut scanner = DeviceScanner::new(true);
+        let mut reader = BufReader::with_capacity(4, &b"abcd\nefgh"[..]);
+        let mut events = Vec::new();
+
+        scanner
+            .scan_line(&mut reader, |event| events.push(event))
+            .unwrap();
+
+        assert_eq!(
+            events,
+            vec![MarkerEvent {
+                kind: MarkerKind::StartOfPacket,
+                position: 4
+            }]
+        );
+    }
+}
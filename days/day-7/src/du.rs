@@ -0,0 +1,348 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::filesystem::{FileSystem, NodeId};
+
+pub struct Disk {
+    pub size: usize,
+    pub required: usize,
+}
+
+impl Disk {
+    /// Bytes that have to be deleted before there is `required` space free
+    pub fn space_to_clear(&self, fs: &FileSystem) -> usize {
+        let free = self.size.saturating_sub(fs.size(fs.root()));
+        self.required.saturating_sub(free)
+    }
+}
+
+pub fn largest_dirs(fs: &FileSystem, count: usize) -> Vec<(NodeId, usize)> {
+    let mut dirs = fs.dirs().map(|dir| (dir, fs.size(dir))).collect::<Vec<_>>();
+    dirs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| fs.path(a.0).cmp(&fs.path(b.0))));
+    dirs.truncate(count);
+    dirs
+}
+
+pub struct ExtensionUsage {
+    pub extension: Option<String>,
+    pub files: usize,
+    pub size: usize,
+}
+
+/// Total file size for each extension, largest first. Files without one are grouped together
+pub fn size_by_extension(fs: &FileSystem) -> Vec<ExtensionUsage> {
+    let mut usage: HashMap<Option<&str>, (usize, usize)> = HashMap::new();
+    for id in fs.ids() {
+        let node = fs.node(id);
+        if !node.is_dir() {
+            let entry = usage.entry(node.extension()).or_default();
+            entry.0 += 1;
+            entry.1 += fs.size(id);
+        }
+    }
+
+    let mut usage = usage
+        .into_iter()
+        .map(|(extension, (files, size))| ExtensionUsage {
+            extension: extension.map(str::to_owned),
+            files,
+            size,
+        })
+        .collect::<Vec<_>>();
+    usage.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then_with(|| a.extension.cmp(&b.extension))
+    });
+    usage
+}
+
+fn depth(fs: &FileSystem, id: NodeId) -> usize {
+    let mut depth = 0;
+    let mut current = id;
+    while let Some(parent) = fs.node(current).parent {
+        depth += 1;
+        current = parent;
+    }
+    depth
+}
+
+pub fn deepest_paths(fs: &FileSystem, count: usize) -> Vec<(NodeId, usize)> {
+    let mut nodes = fs.ids().map(|id| (id, depth(fs, id))).collect::<Vec<_>>();
+    nodes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| fs.path(a.0).cmp(&fs.path(b.0))));
+    nodes.truncate(count);
+    nodes
+}
+
+fn is_within(fs: &FileSystem, id: NodeId, ancestor: NodeId) -> bool {
+    let mut current = Some(id);
+    while let Some(node) = current {
+        if node == ancestor {
+            return true;
+        }
+        current = fs.node(node).parent;
+    }
+    false
+}
+
+/// Finds up to `max_dirs` directories, none inside another, that free at least `needed`
+/// bytes while deleting as little as possible. Ties go to the set with fewer directories.
+/// The search stops at the first set that frees exactly `needed`, but when there is none it
+/// can still take a long time for a large `max_dirs` on a big tree
+pub fn smallest_deletion_set(
+    fs: &FileSystem,
+    needed: usize,
+    max_dirs: usize,
+) -> Option<Vec<NodeId>> {
+    // Directories that are not inside one another never hold more than the whole tree
+    if needed > fs.size(fs.root()) {
+        return None;
+    }
+    // Bigger directories come first, and a parent before a child of the same size, so
+    // everything inside a candidate comes after it
+    let mut candidates = fs.dirs().map(|dir| (dir, fs.size(dir))).collect::<Vec<_>>();
+    candidates.sort_by_key(|(dir, size)| (Reverse(*size), depth(fs, *dir)));
+
+    // The most that candidates from each index on can free together. The best of them are
+    // the ones whose parent comes earlier, so adding a candidate swaps its child
+    // directories for itself
+    let mut reach = vec![0; candidates.len() + 1];
+    for (index, (dir, size)) in candidates.iter().enumerate().rev() {
+        let children = fs
+            .node(*dir)
+            .children()
+            .iter()
+            .filter(|child| fs.node(**child).is_dir())
+            .map(|child| fs.size(*child))
+            .sum::<usize>();
+        reach[index] = reach[index + 1] + size - children;
+    }
+
+    let mut search = DeletionSearch {
+        fs,
+        candidates: &candidates,
+        reach: &reach,
+        needed,
+        max_dirs: 0,
+        chosen: Vec::new(),
+        best: None,
+    };
+    // Allowing one more directory each round means the first set to free exactly what is
+    // needed also has the fewest directories, so nothing after it can do better
+    for max_dirs in 1..=max_dirs.min(candidates.len()) {
+        search.max_dirs = max_dirs;
+        search.extend(0, 0);
+        if search.is_exact() {
+            break;
+        }
+    }
+
+    search.best.map(|(_, dirs)| dirs)
+}
+
+struct DeletionSearch<'a> {
+    fs: &'a FileSystem,
+    // Every directory with its size, largest first
+    candidates: &'a [(NodeId, usize)],
+    // The most the candidates from each index on can free
+    reach: &'a [usize],
+    needed: usize,
+    max_dirs: usize,
+    chosen: Vec<NodeId>,
+    best: Option<(usize, Vec<NodeId>)>,
+}
+
+impl DeletionSearch<'_> {
+    fn is_exact(&self) -> bool {
+        matches!(&self.best, Some((best_total, _)) if *best_total == self.needed)
+    }
+
+    fn is_nested(&self, dir: NodeId) -> bool {
+        self.chosen
+            .iter()
+            .any(|other| is_within(self.fs, dir, *other) || is_within(self.fs, *other, dir))
+    }
+
+    fn offer(&mut self, total: usize, dir: NodeId) {
+        let better = match &self.best {
+            Some((best_total, best_dirs)) => {
+                (total, self.chosen.len() + 1) < (*best_total, best_dirs.len())
+            }
+            None => true,
+        };
+        if better {
+            let mut dirs = self.chosen.clone();
+            dirs.push(dir);
+            self.best = Some((total, dirs));
+        }
+    }
+
+    // Adds directories from `start` on to the chosen ones, which hold `total` bytes and
+    // fall short of what is needed
+    fn extend(&mut self, start: usize, total: usize) {
+        let remaining = &self.candidates[start..];
+        let slots = self.max_dirs - self.chosen.len();
+        if slots == 0 || self.is_exact() {
+            return;
+        }
+
+        // Any one of the directories up to `enough` finishes the set, and since they only
+        // get smaller the last of them that fits is the best way to do it
+        let enough = start + remaining.partition_point(|(_, size)| total + size >= self.needed);
+        if let Some((dir, size)) = self.candidates[start..enough]
+            .iter()
+            .rev()
+            .find(|(dir, _)| !self.is_nested(*dir))
+        {
+            self.offer(total + size, *dir);
+        }
+
+        if slots == 1 {
+            return;
+        }
+        // Whatever follows adds at least the smallest directory there is
+        let smallest = self.candidates.last().map_or(0, |(_, size)| *size);
+        for index in enough..self.candidates.len() {
+            let (dir, size) = self.candidates[index];
+            // Candidates only get smaller, so if filling every slot with this size, or every
+            // candidate left, falls short then so will everything after it
+            if total + (size * slots).min(self.reach[index]) < self.needed || self.is_exact() {
+                break;
+            }
+            if matches!(&self.best, Some((best_total, _)) if total + size + smallest > *best_total)
+            {
+                continue;
+            }
+            if self.is_nested(dir) {
+                continue;
+            }
+
+            self.chosen.push(dir);
+            self.extend(index + 1, total + size);
+            self.chosen.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::{
+        du::{deepest_paths, largest_dirs, size_by_extension, smallest_deletion_set, Disk},
+        filesystem::FileSystem,
+    };
+
+    fn example() -> FileSystem {
+        let mut fs = FileSystem::new();
+        let a = fs.add_dir(fs.root(), "a");
+        let e = fs.add_dir(a, "e");
+        fs.add_file(e, "i", 584);
+        fs.add_file(a, "f", 29116);
+        fs.add_file(a, "g", 2557);
+        fs.add_file(a, "h.lst", 62596);
+        fs.add_file(fs.root(), "b.txt", 14848514);
+        fs.add_file(fs.root(), "c.dat", 8504156);
+        let d = fs.add_dir(fs.root(), "d");
+        fs.add_file(d, "j", 4060174);
+        fs.add_file(d, "d.log", 8033020);
+        fs.add_file(d, "d.ext", 5626152);
+        fs.add_file(d, "k", 7214296);
+        fs
+    }
+
+    #[test]
+    fn test_largest_dirs() {
+        let fs = example();
+
+        let paths = largest_dirs(&fs, 3)
+            .into_iter()
+            .map(|(dir, size)| (fs.path(dir), size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            paths,
+            vec![
+                ("/".to_owned(), 48381165),
+                ("/d".to_owned(), 24933642),
+                ("/a".to_owned(), 94853)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_size_by_extension() {
+        let usage = size_by_extension(&example())
+            .into_iter()
+            .map(|usage| (usage.extension, usage.files, usage.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            usage,
+            vec![
+                (Some("txt".to_owned()), 1, 14848514),
+                (None, 5, 11306727),
+                (Some("dat".to_owned()), 1, 8504156),
+                (Some("log".to_owned()), 1, 8033020),
+                (Some("ext".to_owned()), 1, 5626152),
+                (Some("lst".to_owned()), 1, 62596),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deepest_paths() {
+        let fs = example();
+
+        let deepest = deepest_paths(&fs, 1)
+            .into_iter()
+            .map(|(id, depth)| (fs.path(id), depth))
+            .collect::<Vec<_>>();
+
+        assert_eq!(deepest, vec![("/a/e/i".to_owned(), 3)]);
+    }
+
+    #[test]
+    fn test_space_to_clear() {
+        let disk = Disk {
+            size: 70000000,
+            required: 30000000,
+        };
+
+        assert_eq!(disk.space_to_clear(&example()), 8381165);
+    }
+
+    #[test_case(8381165, 1, &["/d"])]
+    #[test_case(24933642, 1, &["/d"])]
+    #[test_case(24933643, 1, &["/"])]
+    #[test_case(24933643, 2, &["/d", "/a/e"])]
+    #[test_case(95000, 2, &["/d"])]
+    #[test_case(50000000, 3, &[])]
+    #[test_case(24933643, 100, &["/d", "/a/e"])]
+    fn test_smallest_deletion_set(needed: usize, max_dirs: usize, expected: &[&str]) {
+        let fs = example();
+
+        let dirs = smallest_deletion_set(&fs, needed, max_dirs)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|dir| fs.path(dir))
+            .collect::<Vec<_>>();
+
+        assert_eq!(dirs, expected);
+    }
+
+    #[test]
+    fn test_smallest_deletion_set_many_dirs() {
+        // Two hundred sibling directories sized 1000 to 1199, where every pair adding up
+        // to 2012 frees exactly what is needed
+        let mut fs = FileSystem::new();
+        for index in 0..200 {
+            let dir = fs.add_dir(fs.root(), &format!("d{}", index));
+            fs.add_file(dir, "f", 1000 + index);
+        }
+
+        let dirs = smallest_deletion_set(&fs, 2012, 200).unwrap();
+
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs.iter().map(|dir| fs.size(*dir)).sum::<usize>(), 2012);
+    }
+}
@@ -0,0 +1,222 @@
+use std::{collections::HashSet, fmt, fs, io, path::Path};
+
+use crate::filesystem::{FileSystem, NodeId};
+
+/// Builds a filesystem from a real directory. Symlinks are not followed and count as files
+/// of their own size, like `du -ab`
+pub fn from_local_dir(path: &Path) -> io::Result<FileSystem> {
+    let mut fs = FileSystem::new();
+    let root = fs.root();
+    walk_dir(&mut fs, root, path)?;
+    Ok(fs)
+}
+
+fn walk_dir(fs: &mut FileSystem, dir: NodeId, path: &Path) -> io::Result<()> {
+    let mut entries = fs::read_dir(path)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let metadata = fs::symlink_metadata(entry.path())?;
+        if metadata.is_dir() {
+            let child = fs.add_dir(dir, &name);
+            walk_dir(fs, child, &entry.path())?;
+        } else {
+            fs.add_file(dir, &name, metadata.len() as usize);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DuError {
+    Empty,
+    InvalidLine { line: usize, text: String },
+    OutsideRoot { line: usize, path: String },
+}
+
+impl fmt::Display for DuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuError::Empty => write!(f, "du output is empty"),
+            DuError::InvalidLine { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected '<size>\\t<path>', got '{}'",
+                    line, text
+                )
+            }
+            DuError::OutsideRoot { line, path } => {
+                write!(f, "line {}: '{}' is outside the listed root", line, path)
+            }
+        }
+    }
+}
+
+/// Builds a filesystem from the output of `du -ab`. The shortest path is taken as the root
+/// and any path that another path lives under is a directory, so an empty directory comes
+/// back as a file. Directory sizes are worked out from the files as usual rather than taken
+/// from the dump, which would also count the space used by the directory entries
+pub fn from_du_dump(text: &str) -> Result<FileSystem, DuError> {
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || DuError::InvalidLine {
+            line: index + 1,
+            text: line.to_owned(),
+        };
+        let (size, path) = line.split_once('\t').ok_or_else(invalid)?;
+        let size = size.trim().parse::<usize>().map_err(|_| invalid())?;
+        entries.push((index + 1, size, path));
+    }
+
+    let root = entries
+        .iter()
+        .map(|(_, _, path)| *path)
+        .min_by_key(|path| path.len())
+        .ok_or(DuError::Empty)?;
+    let prefix = root.trim_end_matches('/');
+
+    let mut relative = Vec::new();
+    for (line, size, path) in &entries {
+        if *path == root {
+            continue;
+        }
+        let segments = path
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('/'))
+            .map(|rest| rest.split('/').filter(|segment| !segment.is_empty()))
+            .ok_or_else(|| DuError::OutsideRoot {
+                line: *line,
+                path: path.to_string(),
+            })?;
+        relative.push((*size, segments.collect::<Vec<_>>()));
+    }
+
+    let dirs = relative
+        .iter()
+        .flat_map(|(_, segments)| (1..segments.len()).map(|end| &segments[..end]))
+        .collect::<HashSet<_>>();
+
+    let mut fs = FileSystem::new();
+    for (size, segments) in &relative {
+        let Some((name, parents)) = segments.split_last() else {
+            continue;
+        };
+        let parent = parents
+            .iter()
+            .fold(fs.root(), |dir, segment| ensure_dir(&mut fs, dir, segment));
+        if dirs.contains(segments.as_slice()) {
+            ensure_dir(&mut fs, parent, name);
+        } else if fs.child(parent, name).is_none() {
+            fs.add_file(parent, name, *size);
+        }
+    }
+
+    Ok(fs)
+}
+
+fn ensure_dir(fs: &mut FileSystem, parent: NodeId, name: &str) -> NodeId {
+    match fs.child(parent, name) {
+        Some(dir) => dir,
+        None => fs.add_dir(parent, name),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, process};
+
+    use crate::{
+        import::{from_du_dump, from_local_dir, DuError},
+        render::render_listing,
+    };
+
+    #[test]
+    fn test_from_du_dump() {
+        // `du -ab` lists children before their parent, with the root last
+        let dump = [
+            "584\t./a/e/i",
+            "4680\t./a/e",
+            "29116\t./a/f",
+            "2557\t./a/g",
+            "62596\t./a/h.lst",
+            "103045\t./a",
+            "14848514\t./b.txt",
+            "8504156\t./c.dat",
+            "4060174\t./d/j",
+            "8033020\t./d/d.log",
+            "5626152\t./d/d.ext",
+            "7214296\t./d/k",
+            "24937738\t./d",
+            "48393453\t.",
+        ];
+
+        let fs = from_du_dump(&dump.join("\n")).unwrap();
+
+        assert_eq!(fs.size(fs.root()), 48381165);
+        assert_eq!(fs.size(fs.lookup("/a").unwrap()), 94853);
+        assert_eq!(fs.size(fs.lookup("/d").unwrap()), 24933642);
+        assert!(fs.node(fs.lookup("/a/e").unwrap()).is_dir());
+        assert!(!fs.node(fs.lookup("/a/e/i").unwrap()).is_dir());
+    }
+
+    #[test]
+    fn test_from_du_dump_absolute_root() {
+        let fs =
+            from_du_dump("10\t/srv/data/a b/c\n4106\t/srv/data/a b\n4106\t/srv/data/\n").unwrap();
+
+        assert_eq!(fs.size(fs.lookup("/a b").unwrap()), 10);
+        assert_eq!(fs.path(fs.lookup("/a b/c").unwrap()), "/a b/c");
+    }
+
+    #[test]
+    fn test_from_du_dump_errors() {
+        assert_eq!(from_du_dump("").err(), Some(DuError::Empty));
+        assert_eq!(
+            from_du_dump("10 ./a\n10\t.").err(),
+            Some(DuError::InvalidLine {
+                line: 1,
+                text: "10 ./a".to_owned()
+            })
+        );
+        assert_eq!(
+            from_du_dump("10\t./a\n10\t/b\n10\t.").err(),
+            Some(DuError::OutsideRoot {
+                line: 2,
+                path: "/b".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_local_dir() {
+        let root = env::temp_dir().join(format!("day-7-import-{}", process::id()));
+        fs::create_dir_all(root.join("a/e")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("a/e/i"), [0; 584]).unwrap();
+        fs::write(root.join("a/f.txt"), "hello").unwrap();
+        fs::write(root.join("b"), "").unwrap();
+
+        let imported = from_local_dir(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        let expected = [
+            "- / (dir, size=589)",
+            "  - a (dir, size=589)",
+            "    - e (dir, size=584)",
+            "      - i (file, size=584)",
+            "    - f.txt (file, size=5)",
+            "  - b (file, size=0)",
+            "  - empty (dir, size=0)",
+            "",
+        ];
+        assert_eq!(
+            render_listing(&imported.unwrap(), false),
+            expected.join("\n")
+        );
+    }
+}
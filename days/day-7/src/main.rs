@@ -1,208 +1,210 @@
 use std::{
-    cell::RefCell,
+    env, fs,
     io::{self, BufRead},
-    rc::Rc,
+    path::PathBuf,
+    process,
 };
 
-trait Sized {
-    fn size(&self) -> usize;
+use du::{deepest_paths, largest_dirs, size_by_extension, smallest_deletion_set, Disk};
+use filesystem::FileSystem;
+use import::{from_du_dump, from_local_dir};
+use render::{format_size, render_listing, render_transcript, render_tree};
+use shell::{Shell, ShellError};
+
+mod du;
+mod filesystem;
+mod import;
+mod render;
+mod shell;
+
+/// Where the filesystem is read from, a puzzle transcript on stdin by default
+enum Source {
+    Transcript,
+    LocalDir(PathBuf),
+    DuDump(PathBuf),
 }
 
-struct File {
-    pub size: usize,
+struct Options {
+    source: Source,
+    human: bool,
+    disk: Disk,
+    max_dirs: usize,
+    command: Vec<String>,
 }
 
-impl File {
-    fn new(size: &str) -> Self {
-        Self {
-            size: size.parse().unwrap(),
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            source: Source::Transcript,
+            human: false,
+            disk: Disk {
+                size: 70000000,
+                required: 30000000,
+            },
+            max_dirs: 1,
+            command: Vec::new(),
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("{} requires a value", arg))
+            };
+            match arg.as_str() {
+                "--human" => options.human = true,
+                "--from-dir" => options.source = Source::LocalDir(value().into()),
+                "--from-du" => options.source = Source::DuDump(value().into()),
+                "--disk-size" => options.disk.size = value().parse().unwrap(),
+                "--required" => options.disk.required = value().parse().unwrap(),
+                "--max-dirs" => options.max_dirs = value().parse().unwrap(),
+                _ => options.command.push(arg),
+            }
         }
-    }
-}
 
-impl Sized for File {
-    fn size(&self) -> usize {
-        self.size
+        options
     }
 }
 
-struct Dir {
-    parent: Option<Rc<RefCell<Dir>>>,
-    name: String,
-    items: Vec<DirectoryNode>,
-}
-
-impl Dir {
-    fn new(name: &str, parent: Rc<RefCell<Dir>>) -> Self {
-        Self {
-            parent: Some(parent),
-            name: name.to_owned(),
-            items: Vec::new(),
+fn main() {
+    let options = Options::from_args(env::args().skip(1));
+    let loaded = match &options.source {
+        Source::Transcript => {
+            let lines: Vec<_> = io::stdin().lock().lines().map(|ln| ln.unwrap()).collect();
+            create_dir_structure(&lines).map_err(|error| error.to_string())
         }
-    }
-
-    fn new_root(name: &str) -> Self {
-        Self {
-            parent: None,
-            name: name.to_owned(),
-            items: Vec::new(),
+        Source::LocalDir(path) => {
+            from_local_dir(path).map_err(|error| format!("{}: {}", path.display(), error))
         }
-    }
-
-    fn add_node(&mut self, node: DirectoryNode) {
-        self.items.push(node);
-    }
+        Source::DuDump(path) => fs::read_to_string(path)
+            .map_err(|error| format!("{}: {}", path.display(), error))
+            .and_then(|text| from_du_dump(&text).map_err(|error| error.to_string())),
+    };
+    let fs = match loaded {
+        Ok(fs) => fs,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
 
-    fn find_dir(&self, path: &str) -> Option<Rc<RefCell<Dir>>> {
-        for node in &self.items {
-            if let DirectoryNode::Dir(dir) = node {
-                if dir.borrow().name == *path {
-                    return Some(dir.clone());
+    let command = options
+        .command
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let count = |arg: Option<&&str>| arg.map_or(10, |count| count.parse().unwrap());
+    let size = |bytes: usize| format_size(bytes, options.human);
+    match command.as_slice() {
+        [] => solve(&fs, &options.disk),
+        // `day-7 size /a /a/e` reports the size of each path instead of solving the puzzle
+        ["size", paths @ ..] => {
+            for path in paths {
+                match fs.lookup(path) {
+                    Some(id) => println!("{} {}", fs.path(id), size(fs.size(id))),
+                    None => println!("{} not found", path),
                 }
             }
         }
-
-        None
-    }
-}
-
-impl Sized for Dir {
-    fn size(&self) -> usize {
-        self.items.iter().map(|n| n.size()).sum()
-    }
-}
-enum DirectoryNode {
-    File(Rc<RefCell<File>>),
-    Dir(Rc<RefCell<Dir>>),
-}
-
-impl Sized for DirectoryNode {
-    fn size(&self) -> usize {
-        match self {
-            DirectoryNode::File(file) => file.borrow().size(),
-            DirectoryNode::Dir(dir) => dir.borrow().size(),
+        ["listing"] => print!("{}", render_listing(&fs, options.human)),
+        ["tree"] => print!("{}", render_tree(&fs, options.human)),
+        // `day-7 --from-dir ~/src export` turns a real directory into a puzzle input
+        ["export"] => print!("{}", render_transcript(&fs)),
+        ["top", rest @ ..] => {
+            for (dir, dir_size) in largest_dirs(&fs, count(rest.first())) {
+                println!("{}\t{}", size(dir_size), fs.path(dir));
+            }
         }
-    }
-}
-
-enum Command {
-    Cd(String),
-    CdUp,
-    Ls,
-}
-
-fn main() {
-    let lines: Vec<_> = io::stdin().lock().lines().map(|ln| ln.unwrap()).collect();
-    let directory = create_dir_structure(&lines);
-    println!("Part 1: {}", get_sum_dirs_smaller_than(&directory, 100000));
-
-    let space_to_clear = 30000000 - (70000000 - directory.size());
-    let mut dirs_bigger_than_threshold = Vec::new();
-    get_all_dirs_greater_than(&directory, space_to_clear, &mut dirs_bigger_than_threshold);
-
-    dirs_bigger_than_threshold.sort();
-
-    println!("Part 2: {}", dirs_bigger_than_threshold.first().unwrap());
-}
-
-fn create_dir_structure(commands: &[String]) -> DirectoryNode {
-    let root_node = DirectoryNode::Dir(Rc::new(RefCell::new(Dir::new_root("/"))));
-    let mut curr_dir = match &root_node {
-        DirectoryNode::Dir(dir) => dir.clone(),
-        _ => unreachable!(),
-    };
-
-    for line in commands {
-        if let Some(node) = try_parse_dir_node(line, curr_dir.clone()) {
-            curr_dir.borrow_mut().add_node(node);
-            continue;
+        ["extensions"] => {
+            for usage in size_by_extension(&fs) {
+                let extension = usage.extension.as_deref().unwrap_or("(none)");
+                println!("{}\t{}\t{} files", size(usage.size), extension, usage.files);
+            }
+        }
+        ["deepest", rest @ ..] => {
+            for (id, depth) in deepest_paths(&fs, count(rest.first())) {
+                println!("{}\t{}", depth, fs.path(id));
+            }
         }
-        if let Some(command) = try_parse_command(line) {
-            match command {
-                Command::Cd(dir) => {
-                    if let Some(dir) = &curr_dir.clone().borrow().find_dir(&dir) {
-                        curr_dir = dir.clone();
+        // `day-7 free [bytes]` defaults to the space the update needs on the configured disk
+        ["free", rest @ ..] => {
+            let needed = rest
+                .first()
+                .map_or(options.disk.space_to_clear(&fs), |bytes| {
+                    bytes.parse().unwrap()
+                });
+            match smallest_deletion_set(&fs, needed, options.max_dirs) {
+                Some(dirs) => {
+                    let total = dirs.iter().map(|dir| fs.size(*dir)).sum::<usize>();
+                    println!(
+                        "Delete {} to free {} of {} needed",
+                        dirs.len(),
+                        size(total),
+                        size(needed)
+                    );
+                    for dir in dirs {
+                        println!("{}\t{}", size(fs.size(dir)), fs.path(dir));
                     }
                 }
-                Command::CdUp => {
-                    if let Some(dir) = &curr_dir.clone().borrow().parent {
-                        curr_dir = dir.clone();
-                    };
-                }
-                Command::Ls => continue,
+                None => println!(
+                    "Cannot free {} with {} directories",
+                    size(needed),
+                    options.max_dirs
+                ),
             }
-            continue;
         }
+        _ => panic!("Unknown command: {}", command.join(" ")),
     }
-
-    root_node
 }
 
-fn try_parse_command(command_line: &str) -> Option<Command> {
-    let parts = command_line.split(' ').collect::<Vec<_>>();
+fn solve(fs: &FileSystem, disk: &Disk) {
+    println!("Part 1: {}", get_sum_dirs_smaller_than(fs, 100000));
+
+    let space_to_clear = disk.space_to_clear(fs);
+    let mut dirs_bigger_than_threshold = Vec::new();
+    get_all_dirs_greater_than(fs, space_to_clear, &mut dirs_bigger_than_threshold);
+
+    dirs_bigger_than_threshold.sort();
 
-    match parts[1] {
-        "cd" if parts[2] == ".." => Some(Command::CdUp),
-        "cd" => Some(Command::Cd(parts[2].to_owned())),
-        "ls" => Some(Command::Ls),
-        _ => None,
+    match dirs_bigger_than_threshold.first() {
+        Some(size) => println!("Part 2: {}", size),
+        None => println!(
+            "Part 2: Cannot free {} with a single directory",
+            space_to_clear
+        ),
     }
 }
 
-fn try_parse_dir_node(node_text: &str, parent_node: Rc<RefCell<Dir>>) -> Option<DirectoryNode> {
-    let parts = node_text.split(' ').collect::<Vec<_>>();
-
-    match parts[0] {
-        "$" => None,
-        "dir" => Some(DirectoryNode::Dir(Rc::new(RefCell::new(Dir::new(
-            parts[1],
-            parent_node,
-        ))))),
-        _ => Some(DirectoryNode::File(Rc::new(RefCell::new(File::new(
-            parts[0],
-        ))))),
+fn create_dir_structure(commands: &[String]) -> Result<FileSystem, ShellError> {
+    let mut shell = Shell::new();
+    for line in commands {
+        shell.execute(line)?;
     }
+
+    Ok(shell.into_filesystem())
 }
 
-fn get_sum_dirs_smaller_than(dir_node: &DirectoryNode, max_size: usize) -> usize {
-    if let DirectoryNode::Dir(dir) = dir_node {
-        let dir = dir.clone();
-        let size = dir.borrow().size();
-        let curr_size = if dir.borrow().size() <= max_size {
-            size
-        } else {
-            0usize
-        };
-        let child_size = dir
-            .borrow()
-            .items
-            .iter()
-            .map(|m| get_sum_dirs_smaller_than(m, max_size))
-            .sum::<usize>();
-        curr_size + child_size
-    } else {
-        0usize
-    }
+fn get_sum_dirs_smaller_than(fs: &FileSystem, max_size: usize) -> usize {
+    fs.dirs()
+        .map(|dir| fs.size(dir))
+        .filter(|size| *size <= max_size)
+        .sum()
 }
 
-fn get_all_dirs_greater_than(dir_node: &DirectoryNode, min_size: usize, dirs: &mut Vec<usize>) {
-    if let DirectoryNode::Dir(dir) = dir_node {
-        let dir = dir.clone();
-        if dir.borrow().size() >= min_size {
-            dirs.push(dir.borrow().size());
-        }
-        dir.borrow()
-            .items
-            .iter()
-            .for_each(|m| get_all_dirs_greater_than(m, min_size, dirs));
-    };
+fn get_all_dirs_greater_than(fs: &FileSystem, min_size: usize, dirs: &mut Vec<usize>) {
+    dirs.extend(
+        fs.dirs()
+            .map(|dir| fs.size(dir))
+            .filter(|size| *size >= min_size),
+    );
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        create_dir_structure, get_all_dirs_greater_than, get_sum_dirs_smaller_than, DirectoryNode,
-        Sized,
+        create_dir_structure,
+        filesystem::FileSystem,
+        get_all_dirs_greater_than, get_sum_dirs_smaller_than,
+        render::{render_listing, render_transcript},
     };
 
     #[test]
@@ -233,26 +235,21 @@ mod test {
             "7214296 k".to_owned(),
         ]);
 
-        let actual_directory_node = create_dir_structure(&commands);
-        let actual_root_directory = if let DirectoryNode::Dir(dir) = actual_directory_node {
-            dir.clone()
-        } else {
-            panic!("")
-        };
+        let fs = create_dir_structure(&commands).unwrap();
 
-        let actual_a_dir = actual_root_directory.borrow().find_dir("a").unwrap();
+        let actual_a_dir = fs.lookup("/a").unwrap();
 
-        assert_eq!(actual_a_dir.borrow().size(), 94853);
+        assert_eq!(fs.size(actual_a_dir), 94853);
 
-        let actual_e_dir = actual_a_dir.borrow().find_dir("e").unwrap();
+        let actual_e_dir = fs.lookup("/a/e").unwrap();
 
-        assert_eq!(actual_e_dir.borrow().size(), 584);
+        assert_eq!(fs.size(actual_e_dir), 584);
 
-        let actual_d_dir = actual_root_directory.borrow().find_dir("d").unwrap();
+        let actual_d_dir = fs.lookup("/d").unwrap();
 
-        assert_eq!(actual_d_dir.borrow().size(), 24933642);
+        assert_eq!(fs.size(actual_d_dir), 24933642);
 
-        assert_eq!(actual_root_directory.borrow().size(), 48381165);
+        assert_eq!(fs.size(fs.root()), 48381165);
     }
 
     #[test]
@@ -283,8 +280,8 @@ mod test {
             "7214296 k".to_owned(),
         ]);
 
-        let directory_node = create_dir_structure(&commands);
-        let sum_dirs_lt_100k = get_sum_dirs_smaller_than(&directory_node, 100000);
+        let fs = create_dir_structure(&commands).unwrap();
+        let sum_dirs_lt_100k = get_sum_dirs_smaller_than(&fs, 100000);
 
         assert_eq!(sum_dirs_lt_100k, 95437)
     }
@@ -317,14 +314,46 @@ mod test {
             "7214296 k".to_owned(),
         ]);
 
-        let directory_node = create_dir_structure(&commands);
-        let curr_used = directory_node.size();
+        let fs = create_dir_structure(&commands).unwrap();
+        let curr_used = fs.size(fs.root());
         let space_to_clear = 30000000 - (70000000 - curr_used);
         let mut dirs_gt_8381165 = Vec::new();
-        get_all_dirs_greater_than(&directory_node, space_to_clear, &mut dirs_gt_8381165);
+        get_all_dirs_greater_than(&fs, space_to_clear, &mut dirs_gt_8381165);
 
         dirs_gt_8381165.sort();
 
         assert_eq!(*dirs_gt_8381165.first().unwrap(), 24933642);
     }
+
+    #[test]
+    fn test_transcript_round_trip() {
+        // A pseudo-random tree from a linear congruential generator, so the test is repeatable
+        let mut seed = 7u64;
+        let mut next = |bound: usize| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as usize % bound
+        };
+        let mut fs = FileSystem::new();
+        let mut dirs = vec![fs.root()];
+        for index in 0..500 {
+            let parent = dirs[next(dirs.len())];
+            // Some names have spaces in, as real directory trees often do
+            let space = if next(3) == 0 { " " } else { "" };
+            if next(4) == 0 {
+                dirs.push(fs.add_dir(parent, &format!("d{}{}", space, index)));
+            } else {
+                let name = format!("f{}{}.{}", space, index, next(3));
+                fs.add_file(parent, &name, next(100000));
+            }
+        }
+
+        let transcript = render_transcript(&fs);
+        let lines = transcript.lines().map(str::to_owned).collect::<Vec<_>>();
+        let replayed = create_dir_structure(&lines).unwrap();
+
+        assert_eq!(render_listing(&replayed, false), render_listing(&fs, false));
+        assert_eq!(render_transcript(&replayed), transcript);
+    }
 }
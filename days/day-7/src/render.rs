@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use crate::filesystem::{FileSystem, NodeId, NodeKind};
+
+/// Formats a byte count, optionally like `du -h` with a 1024 based unit suffix
+pub fn format_size(size: usize, human: bool) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if !human || size < 1024 {
+        return size.to_string();
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    // Below 10 keep one decimal place, unless rounding would show it as 10.0
+    if value < 9.95 {
+        format!("{:.1}{}", value, UNITS[unit])
+    } else {
+        format!("{:.0}{}", value, UNITS[unit])
+    }
+}
+
+fn sorted_children(fs: &FileSystem, dir: NodeId) -> Vec<NodeId> {
+    let mut children = fs.node(dir).children().to_vec();
+    children.sort_by(|a, b| fs.node(*a).name.cmp(&fs.node(*b).name));
+    children
+}
+
+/// Renders the filesystem like the puzzle's example, with directory sizes included
+pub fn render_listing(fs: &FileSystem, human: bool) -> String {
+    let mut out = String::new();
+    write_listing(fs, fs.root(), 0, human, &mut out);
+    out
+}
+
+fn write_listing(fs: &FileSystem, id: NodeId, depth: usize, human: bool, out: &mut String) {
+    let node = fs.node(id);
+    let kind = if node.is_dir() { "dir" } else { "file" };
+    let size = format_size(fs.size(id), human);
+    writeln!(
+        out,
+        "{}- {} ({}, size={})",
+        "  ".repeat(depth),
+        node.name,
+        kind,
+        size
+    )
+    .unwrap();
+    for child in sorted_children(fs, id) {
+        write_listing(fs, child, depth + 1, human, out);
+    }
+}
+
+/// Renders the filesystem like the `tree` command, directories marked with a trailing `/`
+pub fn render_tree(fs: &FileSystem, human: bool) -> String {
+    let mut out = String::new();
+    writeln!(out, "/ ({})", format_size(fs.size(fs.root()), human)).unwrap();
+    write_tree(fs, fs.root(), "", human, &mut out);
+    out
+}
+
+fn write_tree(fs: &FileSystem, dir: NodeId, prefix: &str, human: bool, out: &mut String) {
+    let children = sorted_children(fs, dir);
+    for (index, child) in children.iter().enumerate() {
+        let last = index == children.len() - 1;
+        let node = fs.node(*child);
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        let slash = if node.is_dir() { "/" } else { "" };
+        writeln!(
+            out,
+            "{}{}{}{} ({})",
+            prefix,
+            branch,
+            node.name,
+            slash,
+            format_size(fs.size(*child), human)
+        )
+        .unwrap();
+        if node.is_dir() {
+            write_tree(fs, *child, &format!("{}{}", prefix, indent), human, out);
+        }
+    }
+}
+
+/// Renders the filesystem as a canonical `$ cd`/`$ ls` transcript that the shell replays
+/// back into the same filesystem. Every directory is listed once, children sorted by name,
+/// and visited with a relative `cd` followed by `cd ..`
+pub fn render_transcript(fs: &FileSystem) -> String {
+    let mut out = String::new();
+    writeln!(out, "$ cd /").unwrap();
+    write_transcript(fs, fs.root(), &mut out);
+    out
+}
+
+fn write_transcript(fs: &FileSystem, dir: NodeId, out: &mut String) {
+    let children = sorted_children(fs, dir);
+    writeln!(out, "$ ls").unwrap();
+    for child in &children {
+        let node = fs.node(*child);
+        match node.kind {
+            NodeKind::Dir { .. } => writeln!(out, "dir {}", node.name).unwrap(),
+            NodeKind::File { size } => writeln!(out, "{} {}", size, node.name).unwrap(),
+        }
+    }
+    for child in children {
+        let node = fs.node(child);
+        if node.is_dir() {
+            writeln!(out, "$ cd {}", node.name).unwrap();
+            write_transcript(fs, child, out);
+            writeln!(out, "$ cd ..").unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::{
+        filesystem::FileSystem,
+        render::{format_size, render_listing, render_transcript, render_tree},
+    };
+
+    fn example() -> FileSystem {
+        let mut fs = FileSystem::new();
+        let d = fs.add_dir(fs.root(), "d");
+        fs.add_file(fs.root(), "b.txt", 14848514);
+        let a = fs.add_dir(fs.root(), "a");
+        fs.add_file(a, "f", 29116);
+        let e = fs.add_dir(a, "e");
+        fs.add_file(e, "i", 584);
+        fs.add_file(d, "k", 7214296);
+        fs
+    }
+
+    #[test]
+    fn test_render_listing() {
+        let expected = [
+            "- / (dir, size=22092510)",
+            "  - a (dir, size=29700)",
+            "    - e (dir, size=584)",
+            "      - i (file, size=584)",
+            "    - f (file, size=29116)",
+            "  - b.txt (file, size=14848514)",
+            "  - d (dir, size=7214296)",
+            "    - k (file, size=7214296)",
+            "",
+        ];
+
+        assert_eq!(render_listing(&example(), false), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_render_tree() {
+        let expected = [
+            "/ (21M)",
+            "├── a/ (29K)",
+            "│   ├── e/ (584)",
+            "│   │   └── i (584)",
+            "│   └── f (28K)",
+            "├── b.txt (14M)",
+            "└── d/ (6.9M)",
+            "    └── k (6.9M)",
+            "",
+        ];
+
+        assert_eq!(render_tree(&example(), true), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_render_transcript() {
+        let expected = [
+            "$ cd /",
+            "$ ls",
+            "dir a",
+            "14848514 b.txt",
+            "dir d",
+            "$ cd a",
+            "$ ls",
+            "dir e",
+            "29116 f",
+            "$ cd e",
+            "$ ls",
+            "584 i",
+            "$ cd ..",
+            "$ cd ..",
+            "$ cd d",
+            "$ ls",
+            "7214296 k",
+            "$ cd ..",
+            "",
+        ];
+
+        assert_eq!(render_transcript(&example()), expected.join("\n"));
+    }
+
+    #[test_case(1023, "1023")]
+    #[test_case(1024, "1.0K")]
+    #[test_case(94853, "93K")]
+    #[test_case(10449487, "10M")]
+    #[test_case(48381165, "46M")]
+    #[test_case(70000000000, "65G")]
+    fn test_format_size_human(size: usize, expected: &str) {
+        assert_eq!(format_size(size, true), expected);
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use crate::filesystem::{FileSystem, NodeId};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ShellError {
+    UnknownCommand { line: usize, command: String },
+    MissingArgument { line: usize, command: String },
+    NoSuchDirectory { line: usize, path: String },
+    UnexpectedOutput { line: usize, text: String },
+    InvalidEntry { line: usize, text: String },
+}
+
+impl fmt::Display for ShellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellError::UnknownCommand { line, command } => {
+                write!(f, "line {}: unknown command '{}'", line, command)
+            }
+            ShellError::MissingArgument { line, command } => {
+                write!(f, "line {}: '{}' is missing an argument", line, command)
+            }
+            ShellError::NoSuchDirectory { line, path } => {
+                write!(f, "line {}: no such directory '{}'", line, path)
+            }
+            ShellError::UnexpectedOutput { line, text } => {
+                write!(f, "line {}: output '{}' does not follow an ls", line, text)
+            }
+            ShellError::InvalidEntry { line, text } => {
+                write!(f, "line {}: cannot read ls entry '{}'", line, text)
+            }
+        }
+    }
+}
+
+enum Command<'a> {
+    Cd(&'a str),
+    Ls,
+}
+
+enum Entry<'a> {
+    Dir(&'a str),
+    File(&'a str, usize),
+}
+
+/// Replays a terminal transcript of `$ cd` and `$ ls` commands and their output into a
+/// filesystem
+pub struct Shell {
+    fs: FileSystem,
+    cwd: NodeId,
+    listing: bool,
+    line: usize,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shell {
+    pub fn new() -> Self {
+        let fs = FileSystem::new();
+        let cwd = fs.root();
+        Self {
+            fs,
+            cwd,
+            listing: false,
+            line: 0,
+        }
+    }
+
+    pub fn into_filesystem(self) -> FileSystem {
+        self.fs
+    }
+
+    pub fn execute(&mut self, text: &str) -> Result<(), ShellError> {
+        self.line += 1;
+        if text.trim().is_empty() {
+            return Ok(());
+        }
+
+        if let Some(command_text) = text.strip_prefix('$') {
+            self.listing = false;
+            match self.parse_command(command_text.trim())? {
+                Command::Cd(path) => {
+                    self.cwd = self
+                        .resolve(path)
+                        .ok_or_else(|| ShellError::NoSuchDirectory {
+                            line: self.line,
+                            path: path.to_owned(),
+                        })?;
+                }
+                Command::Ls => self.listing = true,
+            }
+            return Ok(());
+        }
+
+        if !self.listing {
+            return Err(ShellError::UnexpectedOutput {
+                line: self.line,
+                text: text.to_owned(),
+            });
+        }
+
+        // Listing the same directory again only adds entries that were not seen before
+        match self.parse_entry(text)? {
+            Entry::Dir(name) => {
+                if self.fs.child(self.cwd, name).is_none() {
+                    self.fs.add_dir(self.cwd, name);
+                }
+            }
+            Entry::File(name, size) => {
+                if self.fs.child(self.cwd, name).is_none() {
+                    self.fs.add_file(self.cwd, name, size);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_command<'a>(&self, command_text: &'a str) -> Result<Command<'a>, ShellError> {
+        // Everything after the command is its argument, so paths can hold spaces
+        let (name, argument) = match command_text.split_once(' ') {
+            Some((name, argument)) => (name, argument),
+            None => (command_text, ""),
+        };
+        match name {
+            "cd" if argument.is_empty() => Err(ShellError::MissingArgument {
+                line: self.line,
+                command: command_text.to_owned(),
+            }),
+            "cd" => Ok(Command::Cd(argument)),
+            "ls" => Ok(Command::Ls),
+            _ => Err(ShellError::UnknownCommand {
+                line: self.line,
+                command: command_text.to_owned(),
+            }),
+        }
+    }
+
+    fn parse_entry<'a>(&self, text: &'a str) -> Result<Entry<'a>, ShellError> {
+        let invalid = || ShellError::InvalidEntry {
+            line: self.line,
+            text: text.to_owned(),
+        };
+        let (kind, name) = text.split_once(' ').ok_or_else(invalid)?;
+        if name.is_empty() || name.contains('/') {
+            return Err(invalid());
+        }
+
+        match kind {
+            "dir" => Ok(Entry::Dir(name)),
+            size => Ok(Entry::File(name, size.parse().map_err(|_| invalid())?)),
+        }
+    }
+
+    /// Resolves an absolute or relative path of directories, where `..` at the root stays
+    /// at the root
+    fn resolve(&self, path: &str) -> Option<NodeId> {
+        let start = if path.starts_with('/') {
+            self.fs.root()
+        } else {
+            self.cwd
+        };
+
+        path.split('/')
+            .try_fold(start, |dir, segment| match segment {
+                "" | "." => Some(dir),
+                ".." => Some(self.fs.node(dir).parent.unwrap_or(dir)),
+                name => self
+                    .fs
+                    .child(dir, name)
+                    .filter(|child| self.fs.node(*child).is_dir()),
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::shell::{Shell, ShellError};
+
+    fn run(lines: &[&str]) -> Result<Shell, ShellError> {
+        let mut shell = Shell::new();
+        for line in lines {
+            shell.execute(line)?;
+        }
+        Ok(shell)
+    }
+
+    #[test]
+    fn test_paths_and_repeated_ls() {
+        let shell = run(&[
+            "$ cd /",
+            "$ cd ..",
+            "$ ls",
+            "dir a",
+            "10 b.txt",
+            "$ cd a",
+            "$ ls",
+            "dir e",
+            "$ cd e",
+            "$ ls",
+            "5 i",
+            "$ cd /",
+            "$ ls",
+            "dir a",
+            "10 b.txt",
+            "$ cd a/e",
+            "$ ls",
+            "5 i",
+            "$ cd /a/e/../e",
+            "$ cd ../../a",
+        ])
+        .unwrap();
+        let fs = shell.into_filesystem();
+
+        assert_eq!(fs.size(fs.root()), 15);
+        assert_eq!(fs.node(fs.root()).children().len(), 2);
+        assert_eq!(fs.size(fs.lookup("/a/e").unwrap()), 5);
+    }
+
+    #[test_case(&["$ cd /", "$ pwd"], ShellError::UnknownCommand { line: 2, command: "pwd".to_owned() })]
+    #[test_case(&["$ cd"], ShellError::MissingArgument { line: 1, command: "cd".to_owned() })]
+    #[test_case(&["$ ls", "dir a", "$ cd b"], ShellError::NoSuchDirectory { line: 3, path: "b".to_owned() })]
+    #[test_case(&["$ ls", "5 f", "$ cd f"], ShellError::NoSuchDirectory { line: 3, path: "f".to_owned() })]
+    #[test_case(&["$ cd /", "dir a"], ShellError::UnexpectedOutput { line: 2, text: "dir a".to_owned() })]
+    #[test_case(&["$ ls", "big f"], ShellError::InvalidEntry { line: 2, text: "big f".to_owned() })]
+    fn test_errors(lines: &[&str], expected: ShellError) {
+        assert_eq!(run(lines).err(), Some(expected));
+    }
+}
@@ -0,0 +1,119 @@
+use std::fmt::Write;
+
+use grid::Grid;
+
+use crate::{image::Rgb, Forest};
+
+// Dark blue for the dullest trees through green and yellow to red for the most scenic
+const GRADIENT: [Rgb; 5] = [
+    [20, 24, 82],
+    [34, 102, 190],
+    [46, 170, 96],
+    [238, 204, 52],
+    [222, 52, 36],
+];
+
+/// Colour for a scenic score out of `max`. Scores are spread on a log scale, since a handful
+/// of trees score far above the rest and would otherwise leave everything else dark
+pub fn heat(score: u64, max: u64) -> Rgb {
+    let intensity = if max == 0 {
+        0.0
+    } else {
+        (score as f64).ln_1p() / (max as f64).ln_1p()
+    };
+    let scaled = intensity * (GRADIENT.len() - 1) as f64;
+    let index = (scaled as usize).min(GRADIENT.len() - 2);
+    let fraction = scaled - index as f64;
+    let (from, to) = (GRADIENT[index], GRADIENT[index + 1]);
+
+    std::array::from_fn(|channel| {
+        let from = from[channel] as f64;
+        let to = to[channel] as f64;
+        (from + (to - from) * fraction).round() as u8
+    })
+}
+
+/// The scenic score of every tree as a colour, with trees that cannot be seen from outside
+/// the forest darkened
+pub fn heat_map(forest: &Forest) -> Grid<Rgb> {
+    let visible = forest.visibility_map();
+    let scores = forest.scenic_scores();
+    let max = scores.cells().iter().copied().max().unwrap_or(0);
+
+    Grid::from_fn(scores.columns(), scores.rows(), |x, y| {
+        let colour = heat(scores[(x, y)], max);
+        if visible[(x, y)] {
+            colour
+        } else {
+            colour.map(|channel| channel / 3)
+        }
+    })
+}
+
+/// Draws the forest in the terminal with each tree's height on its heat map colour. Visible
+/// trees have bold white heights and hidden ones grey
+pub fn render_ansi(forest: &Forest) -> String {
+    let visible = forest.visibility_map();
+    let colours = heat_map(forest);
+
+    let mut out = String::new();
+    for y in 0..colours.rows() {
+        for x in 0..colours.columns() {
+            let [red, green, blue] = colours[(x, y)];
+            let style = if visible[(x, y)] { "1;97" } else { "22;90" };
+            write!(
+                out,
+                "\x1b[48;2;{};{};{};{}m{}",
+                red,
+                green,
+                blue,
+                style,
+                forest.get_tree(x, y)
+            )
+            .unwrap();
+        }
+        writeln!(out, "\x1b[0m").unwrap();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        heatmap::{heat, heat_map, render_ansi, GRADIENT},
+        Forest,
+    };
+
+    #[test]
+    fn test_heat() {
+        assert_eq!(heat(0, 100), GRADIENT[0]);
+        assert_eq!(heat(100, 100), GRADIENT[4]);
+        assert_eq!(heat(0, 0), GRADIENT[0]);
+        assert!(heat(10, 100)[0] < heat(50, 100)[0]);
+    }
+
+    #[test]
+    fn test_heat_map() {
+        let lines = Vec::from(["3333".to_owned(), "3133".to_owned(), "3333".to_owned()]);
+        let forest = Forest::from_input(&lines).unwrap();
+
+        let colours = heat_map(&forest);
+
+        // Both inside trees are hidden. The 3 sees past the 1 for the best score of 2
+        assert_eq!(colours[(0, 0)], GRADIENT[0]);
+        assert_eq!(colours[(1, 1)], heat(1, 2).map(|channel| channel / 3));
+        assert_eq!(colours[(2, 1)], GRADIENT[4].map(|channel| channel / 3));
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let lines = Vec::from(["12".to_owned()]);
+        let forest = Forest::from_input(&lines).unwrap();
+
+        assert_eq!(
+            render_ansi(&forest),
+            "\x1b[48;2;20;24;82;1;97m1\x1b[48;2;20;24;82;1;97m2\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use grid::Grid;
+
+pub type Rgb = [u8; 3];
+
+/// Enlarges every pixel to a `factor` by `factor` square
+pub fn upscale(image: &Grid<Rgb>, factor: usize) -> Grid<Rgb> {
+    Grid::from_fn(image.columns() * factor, image.rows() * factor, |x, y| {
+        image[(x / factor, y / factor)]
+    })
+}
+
+/// Binary PPM, the simplest format most image viewers will open
+pub fn encode_ppm(image: &Grid<Rgb>) -> Vec<u8> {
+    let mut out = format!("P6\n{} {}\n255\n", image.columns(), image.rows()).into_bytes();
+    out.extend(image.cells().iter().flatten());
+    out
+}
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// An 8-bit RGB PNG. The image data is kept in stored (uncompressed) deflate blocks, which
+/// keeps the encoder short at the cost of file size
+pub fn encode_png(image: &Grid<Rgb>) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend((image.columns() as u32).to_be_bytes());
+    header.extend((image.rows() as u32).to_be_bytes());
+    // Bit depth 8, colour type 2 (RGB), default compression and filtering, no interlacing
+    header.extend([8, 2, 0, 0, 0]);
+
+    // Every scanline starts with its filter type, 0 for none
+    let mut scanlines = Vec::with_capacity(image.rows() * (1 + image.columns() * 3));
+    for y in 0..image.rows() {
+        scanlines.push(0);
+        scanlines.extend(image.row(y).iter().flatten());
+    }
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut out, b"IHDR", &header);
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend(kind);
+    out.extend(data);
+    let crc = crc32(&out[start..]);
+    out.extend(crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream of stored deflate blocks, each holding at most 65535 bytes
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = u16::MAX as usize;
+    // Deflate with a 32K window and no preset dictionary, the check bits making it a
+    // multiple of 31
+    let mut out = vec![0x78, 0x01];
+    let blocks = data.len().div_ceil(BLOCK_SIZE).max(1);
+    for index in 0..blocks {
+        let block =
+            &data[(index * BLOCK_SIZE).min(data.len())..((index + 1) * BLOCK_SIZE).min(data.len())];
+        let last = index == blocks - 1;
+        out.push(last as u8);
+        out.extend((block.len() as u16).to_le_bytes());
+        out.extend((!(block.len() as u16)).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+    let (a, b) = data.iter().fold((1, 0), |(a, b), byte| {
+        let a = (a + *byte as u32) % MODULUS;
+        (a, (b + a) % MODULUS)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test {
+    use grid::Grid;
+
+    use crate::image::{adler32, crc32, encode_png, encode_ppm, upscale, zlib_stored};
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    // Reads back the stored blocks written by `zlib_stored`
+    fn inflate_stored(stream: &[u8]) -> Vec<u8> {
+        assert_eq!(&stream[..2], &[0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut position = 2;
+        loop {
+            let last = stream[position] == 1;
+            let len = u16::from_le_bytes([stream[position + 1], stream[position + 2]]) as usize;
+            let nlen = u16::from_le_bytes([stream[position + 3], stream[position + 4]]);
+            assert_eq!(nlen, !(len as u16));
+            data.extend(&stream[position + 5..position + 5 + len]);
+            position += 5 + len;
+            if last {
+                break;
+            }
+        }
+        let checksum = u32::from_be_bytes(stream[position..position + 4].try_into().unwrap());
+        assert_eq!(checksum, adler32(&data));
+        assert_eq!(position + 4, stream.len());
+        data
+    }
+
+    #[test]
+    fn test_zlib_stored_blocks() {
+        let data = (0..150_000)
+            .map(|index| (index % 251) as u8)
+            .collect::<Vec<_>>();
+
+        assert_eq!(inflate_stored(&zlib_stored(&data)), data);
+        assert_eq!(inflate_stored(&zlib_stored(&[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_encode_png() {
+        let image = Grid::new(2, 1, vec![[255, 0, 0], [0, 0, 255]]);
+
+        let png = encode_png(&image);
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut position = 8;
+        while position < png.len() {
+            let len = u32::from_be_bytes(png[position..position + 4].try_into().unwrap()) as usize;
+            let kind = &png[position + 4..position + 8];
+            let data = &png[position + 8..position + 8 + len];
+            let crc = u32::from_be_bytes(
+                png[position + 8 + len..position + 12 + len]
+                    .try_into()
+                    .unwrap(),
+            );
+            assert_eq!(crc, crc32(&png[position + 4..position + 8 + len]));
+            chunks.push((kind, data));
+            position += 12 + len;
+        }
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks[0],
+            (&b"IHDR"[..], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0][..])
+        );
+        assert_eq!(chunks[1].0, b"IDAT");
+        assert_eq!(inflate_stored(chunks[1].1), vec![0, 255, 0, 0, 0, 0, 255]);
+        assert_eq!(chunks[2], (&b"IEND"[..], &[][..]));
+    }
+
+    #[test]
+    fn test_encode_ppm_upscaled() {
+        let image = Grid::new(1, 1, vec![[1, 2, 3]]);
+
+        let ppm = encode_ppm(&upscale(&image, 2));
+
+        assert_eq!(
+            ppm,
+            b"P6\n2 2\n255\n\x01\x02\x03\x01\x02\x03\x01\x02\x03\x01\x02\x03"
+        );
+    }
+}
@@ -1,156 +1,296 @@
 use std::{
-    cell::RefCell,
+    env, fs,
     io::{self, BufRead},
-    rc::Rc,
+    process,
 };
 
+use grid::{Grid, GridError};
+use heatmap::{heat_map, render_ansi};
+use image::{encode_png, encode_ppm, upscale};
+use sight::{parse_rule, visibility_map_along, visibility_map_from, HeightRule, CARDINAL, COMPASS};
+
+mod heatmap;
+mod image;
+mod sight;
+
 struct Forest {
-    trees: Vec<u32>,
-    columns: usize,
-    rows: usize,
+    trees: Grid<u32>,
 }
 
 impl Forest {
-    pub fn from_input(lines: &[String]) -> Self {
-        let rows = lines.len();
-        let columns = lines[0].len();
-        let trees: Vec<u32> = lines
-            .iter()
-            .flat_map(|m| {
-                m.chars()
-                    .filter(|f| f.is_numeric())
-                    .map(|c| c.to_digit(10).unwrap())
-            })
-            .collect();
-        Self {
-            rows,
-            columns,
-            trees,
-        }
+    pub fn from_input(lines: &[String]) -> Result<Self, GridError> {
+        Ok(Self {
+            trees: Grid::from_digits(lines)?,
+        })
     }
 
     pub fn get_tree(&self, x: usize, y: usize) -> u32 {
-        self.trees[x + y * self.columns]
+        self.trees[(x, y)]
     }
 
-    pub fn is_visible(&self, x: usize, y: usize) -> bool {
-        if x == 0 || x == self.rows - 1 || y == 0 || y == self.columns - 1 {
-            true
-        } else {
-            self.is_visible_x(x, y) || self.is_visible_y(x, y)
+    /// Which trees can be seen from outside the forest. Each row and column is swept from
+    /// both ends keeping the tallest tree so far, so every tree is looked at four times
+    /// rather than scanning outwards from each one
+    pub fn visibility_map(&self) -> Grid<bool> {
+        let mut visible = self.trees.map(|_| false);
+        for y in 0..self.trees.rows() {
+            let trees = self.trees.row(y);
+            let row = visible.row_mut(y);
+            Self::sweep(trees.iter().zip(row.iter_mut()));
+            Self::sweep(trees.iter().zip(row.iter_mut()).rev());
         }
-    }
+        // Columns are swept a whole row at a time, which keeps to the order trees are stored in
+        self.sweep_columns(&mut visible, 0..self.trees.rows());
+        self.sweep_columns(&mut visible, (0..self.trees.rows()).rev());
 
-    fn is_visible_x(&self, x: usize, y: usize) -> bool {
-        let target_tree = self.get_tree(x, y);
-        let mut visible_before = true;
-        for test_x in 0..x {
-            let test_tree = self.get_tree(test_x, y);
-            if target_tree <= test_tree {
-                visible_before = false;
-                break;
-            }
-        }
+        visible
+    }
 
-        let mut visible_after = true;
-        for test_x in x + 1..self.columns {
-            let test_tree = self.get_tree(test_x, y);
-            if target_tree <= test_tree {
-                visible_after = false;
-                break;
+    fn sweep<'a>(line: impl Iterator<Item = (&'a u32, &'a mut bool)>) {
+        let mut tallest = None;
+        for (tree, visible) in line {
+            if tallest < Some(*tree) {
+                *visible = true;
+                tallest = Some(*tree);
             }
         }
-
-        visible_before || visible_after
     }
 
-    fn is_visible_y(&self, x: usize, y: usize) -> bool {
-        let target_tree = self.get_tree(x, y);
-        let mut visible_before = true;
-        for test_y in 0..y {
-            let test_tree = self.get_tree(x, test_y);
-            if target_tree <= test_tree {
-                visible_before = false;
-                break;
+    fn sweep_columns(&self, visible: &mut Grid<bool>, rows: impl Iterator<Item = usize>) {
+        let mut tallest = vec![None; self.trees.columns()];
+        for y in rows {
+            let line = self.trees.row(y).iter().zip(visible.row_mut(y));
+            for (tallest, (tree, visible)) in tallest.iter_mut().zip(line) {
+                if *tallest < Some(*tree) {
+                    *visible = true;
+                    *tallest = Some(*tree);
+                }
             }
         }
+    }
 
-        let mut visible_after = true;
-        for test_y in y + 1..self.columns {
-            let test_tree = self.get_tree(x, test_y);
-            if target_tree <= test_tree {
-                visible_after = false;
-                break;
-            }
+    /// The scenic score of every tree. Each line is walked once in both directions with a
+    /// stack of the trees that could still block the view, so a tree finds its viewing
+    /// distance without scanning back over the line
+    pub fn scenic_scores(&self) -> Grid<u64> {
+        let mut scores = self.trees.map(|_| 1);
+        for y in 0..self.trees.rows() {
+            let trees = self.trees.row(y);
+            let row = scores.row_mut(y);
+            Self::multiply_view_distances(trees.iter().zip(row.iter_mut()));
+            Self::multiply_view_distances(trees.iter().zip(row.iter_mut()).rev());
         }
+        self.multiply_column_view_distances(&mut scores, 0..self.trees.rows());
+        self.multiply_column_view_distances(&mut scores, (0..self.trees.rows()).rev());
 
-        visible_before || visible_after
+        scores
     }
 
-    pub fn get_scenic_score(&self, x: usize, y: usize) -> u32 {
-        let target_tree = self.get_tree(x, y);
-        let mut neg_x = 0;
-        for test_x in (0..x).rev() {
-            let test_tree = self.get_tree(test_x, y);
-            neg_x = neg_x + 1;
-            if target_tree <= test_tree {
-                break;
-            }
+    fn multiply_view_distances<'a>(line: impl Iterator<Item = (&'a u32, &'a mut u64)>) {
+        let mut blockers = Vec::new();
+        for (position, (tree, score)) in line.enumerate() {
+            *score *= Self::view_distance(&mut blockers, position, *tree) as u64;
         }
+    }
 
-        let mut pos_x = 0;
-        for test_x in x + 1..self.columns {
-            let test_tree = self.get_tree(test_x, y);
-            pos_x = pos_x + 1;
-            if target_tree <= test_tree {
-                break;
+    // The same walk down every column at once, a row at a time to follow the storage order
+    fn multiply_column_view_distances(
+        &self,
+        scores: &mut Grid<u64>,
+        rows: impl Iterator<Item = usize>,
+    ) {
+        let mut blockers = vec![Vec::new(); self.trees.columns()];
+        for (position, y) in rows.enumerate() {
+            let line = self.trees.row(y).iter().zip(scores.row_mut(y));
+            for (blockers, (tree, score)) in blockers.iter_mut().zip(line) {
+                *score *= Self::view_distance(blockers, position, *tree) as u64;
             }
         }
+    }
 
-        let mut neg_y = 0;
-        for test_y in (0..y).rev() {
-            let test_tree = self.get_tree(x, test_y);
-            neg_y = neg_y + 1;
-            if target_tree <= test_tree {
-                break;
-            }
+    /// How far back along a line the tree at `position` can see. `blockers` holds the
+    /// positions and heights of earlier trees, each shorter than every tree before it. A
+    /// tree pops anything shorter, since nothing later can see past the tree itself
+    fn view_distance(blockers: &mut Vec<(usize, u32)>, position: usize, tree: u32) -> usize {
+        while blockers.last().is_some_and(|(_, height)| *height < tree) {
+            blockers.pop();
         }
+        let distance = match blockers.last() {
+            Some((blocker, _)) => position - blocker,
+            None => position,
+        };
+        blockers.push((position, tree));
 
-        let mut pos_y = 0;
-        for test_y in y + 1..self.rows {
-            let test_tree = self.get_tree(x, test_y);
-            pos_y = pos_y + 1;
-            if target_tree <= test_tree {
-                break;
-            }
+        distance
+    }
+
+    /// The `count` most scenic trees as `(x, y, score)`, highest score first and ties in
+    /// reading order
+    pub fn most_scenic(&self, count: usize) -> Vec<(usize, usize, u64)> {
+        let scores = self.scenic_scores();
+        let mut positions = scores.positions().collect::<Vec<_>>();
+        let order = |a: &(usize, usize), b: &(usize, usize)| {
+            scores[*b]
+                .cmp(&scores[*a])
+                .then((a.1, a.0).cmp(&(b.1, b.0)))
+        };
+        if count < positions.len() {
+            positions.select_nth_unstable_by(count, order);
+            positions.truncate(count);
         }
+        positions.sort_unstable_by(order);
+
+        positions
+            .into_iter()
+            .map(|(x, y)| (x, y, scores[(x, y)]))
+            .collect()
+    }
+}
+
+// The per-tree scans the whole-grid passes replaced, kept as a reference for the tests
+#[cfg(test)]
+impl Forest {
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        let tree = self.get_tree(x, y);
+        grid::Direction::ALL.into_iter().any(|direction| {
+            self.trees
+                .ray(x, y, direction)
+                .all(|(_, other)| *other < tree)
+        })
+    }
 
-        neg_x * neg_y * pos_x * pos_y
+    pub fn get_scenic_score(&self, x: usize, y: usize) -> u64 {
+        let tree = self.get_tree(x, y);
+        grid::Direction::ALL
+            .into_iter()
+            .map(|direction| {
+                let mut distance = 0;
+                for (_, other) in self.trees.ray(x, y, direction) {
+                    distance += 1;
+                    if *other >= tree {
+                        break;
+                    }
+                }
+                distance
+            })
+            .product()
     }
 }
 
 fn main() {
     let lines: Vec<_> = io::stdin().lock().lines().map(|ln| ln.unwrap()).collect();
-    let forest = Forest::from_input(&lines);
-    let mut count = 0;
-    let mut max_scenic_score = 0;
-    for x in 0..forest.columns {
-        for y in 0..forest.rows {
-            if forest.is_visible(x, y) {
-                count = count + 1;
+    let forest = match Forest::from_input(&lines) {
+        Ok(forest) => forest,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [] => {
+            let count = forest
+                .visibility_map()
+                .cells()
+                .iter()
+                .filter(|visible| **visible)
+                .count();
+            let max_scenic_score = forest
+                .scenic_scores()
+                .cells()
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or(0);
+            println!("Part 1: {}", count);
+            println!("Part 2: {}", max_scenic_score);
+        }
+        // `day-8 top 5` lists the most scenic trees with their coordinates
+        ["top", rest @ ..] => {
+            let count = rest.first().map_or(10, |count| count.parse().unwrap());
+            for (x, y, score) in forest.most_scenic(count) {
+                let height = forest.get_tree(x, y);
+                println!("({}, {}) height {} score {}", x, y, height, score);
             }
-            let scenic_score = forest.get_scenic_score(x, y);
-            if scenic_score > max_scenic_score {
-                max_scenic_score = scenic_score;
+        }
+        ["heatmap"] => print!("{}", render_ansi(&forest)),
+        // `day-8 image forest.png 4` draws the heat map with each tree 4 pixels wide, as a
+        // PNG or PPM going by the extension
+        ["image", path, rest @ ..] => {
+            let scale = match rest.first().map_or(Ok(4), |scale| scale.parse::<usize>()) {
+                Ok(scale) if scale > 0 => scale,
+                _ => {
+                    eprintln!("{}: expected a scale of at least 1", rest[0]);
+                    process::exit(1);
+                }
+            };
+            let image = upscale(&heat_map(&forest), scale);
+            let data = match path.rsplit_once('.').map(|(_, extension)| extension) {
+                Some("png") => encode_png(&image),
+                Some("ppm") => encode_ppm(&image),
+                _ => {
+                    eprintln!("{}: expected a .png or .ppm file", path);
+                    process::exit(1);
+                }
+            };
+            if let Err(error) = fs::write(path, data) {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
             }
         }
+        // `day-8 visible compass not-taller` counts the trees seen from outside the forest
+        // along rows, columns and diagonals when only taller trees block the view
+        ["visible", kind @ ("edges" | "compass"), rest @ ..] => {
+            let directions: &[_] = if *kind == "edges" {
+                &CARDINAL
+            } else {
+                &COMPASS
+            };
+            let visible = visibility_map_along(&forest, directions, read_rule(rest));
+            println!(
+                "{}",
+                visible.cells().iter().filter(|visible| **visible).count()
+            );
+        }
+        // `day-8 visible from -1 -1 sightline=12` counts the trees an observer standing
+        // just off the top left corner can see
+        ["visible", "from", x, y, rest @ ..] => {
+            let observer = (x.parse().unwrap(), y.parse().unwrap());
+            let visible = visibility_map_from(&forest, observer, read_rule(rest));
+            println!(
+                "{}",
+                visible.cells().iter().filter(|visible| **visible).count()
+            );
+        }
+        _ => panic!("Unknown command: {}", args.join(" ")),
+    }
+}
+
+fn read_rule(args: &[&str]) -> HeightRule {
+    match args.first() {
+        None => HeightRule::Shorter,
+        Some(text) => parse_rule(text).unwrap_or_else(|| {
+            eprintln!(
+                "{}: expected shorter, not-taller or sightline=<eye height>",
+                text
+            );
+            process::exit(1);
+        }),
     }
-    println!("Part 1: {}", count);
-    println!("Part 2: {}", max_scenic_score);
 }
 
 #[cfg(test)]
 mod test {
+    use std::time::Instant;
+
+    use grid::{Grid, GridError};
+
     use crate::Forest;
     use test_case::test_case;
 
@@ -164,11 +304,11 @@ mod test {
             "35390".to_owned(),
         ]);
 
-        let forest = Forest::from_input(&lines);
+        let forest = Forest::from_input(&lines).unwrap();
 
-        assert_eq!(forest.columns, 5);
-        assert_eq!(forest.rows, 5);
-        assert_eq!(forest.trees.len(), 25);
+        assert_eq!(forest.trees.columns(), 5);
+        assert_eq!(forest.trees.rows(), 5);
+        assert_eq!(forest.trees.cells().len(), 25);
     }
 
     #[test]
@@ -181,7 +321,7 @@ mod test {
             "35390".to_owned(),
         ]);
 
-        let forest = Forest::from_input(&lines);
+        let forest = Forest::from_input(&lines).unwrap();
 
         assert_eq!(forest.get_tree(0, 0), 3);
         assert_eq!(forest.get_tree(1, 0), 0);
@@ -204,7 +344,7 @@ mod test {
             "35390".to_owned(),
         ]);
 
-        let forest = Forest::from_input(&lines);
+        let forest = Forest::from_input(&lines).unwrap();
 
         assert_eq!(forest.is_visible(x, y), expect_visible);
     }
@@ -219,8 +359,206 @@ mod test {
             "35390".to_owned(),
         ]);
 
-        let forest = Forest::from_input(&lines);
+        let forest = Forest::from_input(&lines).unwrap();
 
         assert_eq!(forest.get_scenic_score(2, 3), 8);
     }
+
+    #[test]
+    fn test_visibility_map() {
+        let lines = Vec::from([
+            "30373".to_owned(),
+            "25512".to_owned(),
+            "65332".to_owned(),
+            "33549".to_owned(),
+            "35390".to_owned(),
+        ]);
+
+        let forest = Forest::from_input(&lines).unwrap();
+        let visible = forest.visibility_map();
+
+        assert_eq!(
+            visible.cells().iter().filter(|visible| **visible).count(),
+            21
+        );
+        for ((x, y), visible) in visible.iter() {
+            assert_eq!(*visible, forest.is_visible(x, y));
+        }
+    }
+
+    // Random heights from a xorshift generator
+    fn random_forest(rows: usize, columns: usize, seed: u64) -> Forest {
+        let mut state = seed;
+        let lines = (0..rows)
+            .map(|_| {
+                (0..columns)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        char::from(b'0' + (state % 10) as u8)
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        Forest::from_input(&lines).unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_visibility_map() {
+        let forest = random_forest(5000, 5000, 0x2545f4914f6cdd1d);
+
+        let start = Instant::now();
+        let swept = forest
+            .visibility_map()
+            .cells()
+            .iter()
+            .filter(|visible| **visible)
+            .count();
+        let swept_time = start.elapsed();
+
+        let start = Instant::now();
+        let scanned = forest
+            .trees
+            .positions()
+            .filter(|(x, y)| forest.is_visible(*x, *y))
+            .count();
+        let scanned_time = start.elapsed();
+
+        println!("sweeps: {:?}, per tree: {:?}", swept_time, scanned_time);
+        assert_eq!(swept, scanned);
+    }
+
+    #[test]
+    fn test_scenic_scores() {
+        let lines = Vec::from([
+            "30373".to_owned(),
+            "25512".to_owned(),
+            "65332".to_owned(),
+            "33549".to_owned(),
+            "35390".to_owned(),
+        ]);
+
+        let forest = Forest::from_input(&lines).unwrap();
+        let scores = forest.scenic_scores();
+
+        for ((x, y), score) in scores.iter() {
+            assert_eq!(*score, forest.get_scenic_score(x, y));
+        }
+        assert_eq!(forest.most_scenic(3), vec![(2, 3, 8), (1, 2, 6), (2, 1, 4)]);
+    }
+
+    #[test]
+    fn test_scenic_score_past_u32() {
+        // A lone tall tree sees to every edge of a large forest, for a score of
+        // 300 * 299 * 300 * 299
+        let forest = Forest {
+            trees: Grid::from_fn(600, 600, |x, y| if (x, y) == (300, 300) { 9 } else { 0 }),
+        };
+
+        assert_eq!(forest.most_scenic(1), vec![(300, 300, 8_046_090_000)]);
+        assert_eq!(forest.get_scenic_score(300, 300), 8_046_090_000);
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_scenic_scores() {
+        // Digit heights keep most views short, which suits the per-tree scan, while on a
+        // slope every tree sees all the way back to the edge
+        let slope = Forest {
+            trees: Grid::from_fn(1000, 1000, |x, y| (x + y) as u32),
+        };
+        let forests = [
+            ("random", random_forest(5000, 5000, 0x9e3779b97f4a7c15)),
+            ("slope", slope),
+        ];
+
+        for (name, forest) in forests {
+            let start = Instant::now();
+            let stacked = forest.scenic_scores();
+            let stacked_time = start.elapsed();
+
+            let start = Instant::now();
+            let scanned = Grid::from_fn(forest.trees.columns(), forest.trees.rows(), |x, y| {
+                forest.get_scenic_score(x, y)
+            });
+            let scanned_time = start.elapsed();
+
+            println!(
+                "{}: stacks: {:?}, per tree: {:?}",
+                name, stacked_time, scanned_time
+            );
+            assert_eq!(stacked, scanned);
+        }
+    }
+
+    #[test_case(&["123", "45", "678"], GridError::RaggedRow { row: 2, expected: 3, found: 2 })]
+    #[test_case(&["12", "3x"], GridError::InvalidCell { row: 2, column: 2, found: 'x' })]
+    fn test_from_input_errors(lines: &[&str], expected: GridError) {
+        let lines = lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(Forest::from_input(&lines).err(), Some(expected));
+    }
+
+    // Straightforward answers from a grid of rows, looking outwards from the tree at (x, y)
+    fn reference_views(grid: &[Vec<u32>], x: usize, y: usize) -> [Vec<u32>; 4] {
+        let row = &grid[y];
+        let column = grid.iter().map(|row| row[x]).collect::<Vec<_>>();
+        [
+            row[..x].iter().rev().copied().collect(),
+            row[x + 1..].to_vec(),
+            column[..y].iter().rev().copied().collect(),
+            column[y + 1..].to_vec(),
+        ]
+    }
+
+    fn reference_visible(grid: &[Vec<u32>], x: usize, y: usize) -> bool {
+        let tree = grid[y][x];
+        reference_views(grid, x, y)
+            .iter()
+            .any(|view| view.iter().all(|other| *other < tree))
+    }
+
+    fn reference_scenic_score(grid: &[Vec<u32>], x: usize, y: usize) -> u64 {
+        let tree = grid[y][x];
+        reference_views(grid, x, y)
+            .iter()
+            .map(|view| match view.iter().position(|other| *other >= tree) {
+                Some(blocker) => blocker as u64 + 1,
+                None => view.len() as u64,
+            })
+            .product()
+    }
+
+    #[test]
+    fn test_rectangular_forests_match_reference() {
+        let mut seed = 1;
+        for rows in 1..=9 {
+            for columns in 1..=9 {
+                for _ in 0..5 {
+                    seed += 1;
+                    let forest = random_forest(rows, columns, seed);
+                    let grid = (0..rows)
+                        .map(|y| forest.trees.row(y).to_vec())
+                        .collect::<Vec<_>>();
+                    let visible = forest.visibility_map();
+                    let scores = forest.scenic_scores();
+
+                    for (x, y) in forest.trees.positions() {
+                        let expected_visible = reference_visible(&grid, x, y);
+                        let expected_score = reference_scenic_score(&grid, x, y);
+                        assert_eq!(forest.is_visible(x, y), expected_visible);
+                        assert_eq!(visible[(x, y)], expected_visible);
+                        assert_eq!(forest.get_scenic_score(x, y), expected_score);
+                        assert_eq!(scores[(x, y)], expected_score);
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,267 @@
+use grid::{bresenham, Grid};
+
+use crate::Forest;
+
+/// The four edge directions of the puzzle itself
+pub const CARDINAL: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+/// The cardinal directions along with the four diagonals
+pub const COMPASS: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+    (1, 1),
+];
+
+/// Which trees on the way block the view of a tree
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeightRule {
+    /// The puzzle's rule, anything at least as tall as the tree blocks it
+    Shorter,
+    /// Only taller trees block, so a tree can be seen over ones of its own height
+    NotTaller,
+    /// The observer's eye is at `eye_height` and a tree blocks when it reaches the straight
+    /// line from there to the top of the tree being looked at
+    Sightline { eye_height: f64 },
+}
+
+/// Reads `shorter`, `not-taller` or `sightline=<eye height>`
+pub fn parse_rule(text: &str) -> Option<HeightRule> {
+    match text.split_once('=') {
+        None if text == "shorter" => Some(HeightRule::Shorter),
+        None if text == "not-taller" => Some(HeightRule::NotTaller),
+        Some(("sightline", eye_height)) => Some(HeightRule::Sightline {
+            eye_height: eye_height.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+impl HeightRule {
+    /// Whether a tree of height `blocker`, a `fraction` of the way from the observer to a
+    /// tree of height `target`, is in the way
+    fn blocks(self, blocker: u32, target: u32, fraction: f64) -> bool {
+        match self {
+            HeightRule::Shorter => blocker >= target,
+            HeightRule::NotTaller => blocker > target,
+            HeightRule::Sightline { eye_height } => {
+                blocker as f64 >= eye_height + (target as f64 - eye_height) * fraction
+            }
+        }
+    }
+}
+
+fn tree_at(forest: &Forest, (x, y): (isize, isize)) -> Option<u32> {
+    let x = usize::try_from(x).ok()?;
+    let y = usize::try_from(y).ok()?;
+    forest.trees.get(x, y).copied()
+}
+
+/// Whether an observer at `observer`, which can be on the forest or anywhere off it, can see
+/// the tree at `(x, y)`. Trees on the Bresenham line between the two may block the view; the
+/// observer's own spot never does
+pub fn visible_from(
+    forest: &Forest,
+    observer: (isize, isize),
+    (x, y): (usize, usize),
+    rule: HeightRule,
+) -> bool {
+    let target_position = (x as isize, y as isize);
+    let target = forest.get_tree(x, y);
+    let distance =
+        |(px, py): (isize, isize)| ((px - observer.0) as f64).hypot((py - observer.1) as f64);
+    let total = distance(target_position);
+
+    bresenham(observer, target_position)
+        .filter(|position| *position != observer && *position != target_position)
+        .all(|position| match tree_at(forest, position) {
+            Some(blocker) => !rule.blocks(blocker, target, distance(position) / total),
+            None => true,
+        })
+}
+
+/// Whether the tree at `(x, y)` can be seen from outside the forest looking back along
+/// `direction`, which can be any step such as `(1, 1)` for a diagonal or `(2, 1)` for a
+/// shallower angle. The observer stands at the first point past the edge
+pub fn visible_along(
+    forest: &Forest,
+    (x, y): (usize, usize),
+    direction: (isize, isize),
+    rule: HeightRule,
+) -> bool {
+    assert_ne!(direction, (0, 0), "a direction needs a step");
+    let mut observer = (x as isize, y as isize);
+    while tree_at(forest, observer).is_some() {
+        observer = (observer.0 + direction.0, observer.1 + direction.1);
+    }
+
+    visible_from(forest, observer, (x, y), rule)
+}
+
+/// Every tree that can be seen from `observer`
+pub fn visibility_map_from(
+    forest: &Forest,
+    observer: (isize, isize),
+    rule: HeightRule,
+) -> Grid<bool> {
+    Grid::from_fn(forest.trees.columns(), forest.trees.rows(), |x, y| {
+        visible_from(forest, observer, (x, y), rule)
+    })
+}
+
+/// Every tree that can be seen from outside the forest along at least one of `directions`
+pub fn visibility_map_along(
+    forest: &Forest,
+    directions: &[(isize, isize)],
+    rule: HeightRule,
+) -> Grid<bool> {
+    Grid::from_fn(forest.trees.columns(), forest.trees.rows(), |x, y| {
+        directions
+            .iter()
+            .any(|direction| visible_along(forest, (x, y), *direction, rule))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::{
+        sight::{
+            parse_rule, visibility_map_along, visibility_map_from, visible_along, visible_from,
+            HeightRule, CARDINAL, COMPASS,
+        },
+        Forest,
+    };
+
+    fn example() -> Forest {
+        let lines = Vec::from([
+            "30373".to_owned(),
+            "25512".to_owned(),
+            "65332".to_owned(),
+            "33549".to_owned(),
+            "35390".to_owned(),
+        ]);
+        Forest::from_input(&lines).unwrap()
+    }
+
+    fn count(visible: &grid::Grid<bool>) -> usize {
+        visible.cells().iter().filter(|visible| **visible).count()
+    }
+
+    #[test]
+    fn test_cardinal_matches_puzzle() {
+        let forest = example();
+
+        let along = visibility_map_along(&forest, &CARDINAL, HeightRule::Shorter);
+
+        assert_eq!(along, forest.visibility_map());
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let forest = example();
+
+        // The 4 at (3, 3) is hidden along rows and columns, but the 0 in the bottom right
+        // corner leaves it in sight diagonally
+        assert!(!forest.visibility_map()[(3, 3)]);
+        assert!(visible_along(&forest, (3, 3), (1, 1), HeightRule::Shorter));
+        assert!(!visible_along(
+            &forest,
+            (3, 3),
+            (-1, -1),
+            HeightRule::Shorter
+        ));
+        assert_eq!(
+            count(&visibility_map_along(
+                &forest,
+                &COMPASS,
+                HeightRule::Shorter
+            )),
+            22
+        );
+    }
+
+    #[test]
+    fn test_any_angle() {
+        let lines = Vec::from([
+            "90909".to_owned(),
+            "00000".to_owned(),
+            "90509".to_owned(),
+            "00000".to_owned(),
+            "90909".to_owned(),
+        ]);
+        let forest = Forest::from_input(&lines).unwrap();
+
+        // Every row, column and diagonal out from the 5 ends at a 9, but the line up and to
+        // the right at a steeper angle only crosses 0s
+        assert!(COMPASS.iter().all(|direction| !visible_along(
+            &forest,
+            (2, 2),
+            *direction,
+            HeightRule::Shorter
+        )));
+        assert!(visible_along(&forest, (2, 2), (1, -2), HeightRule::Shorter));
+    }
+
+    #[test]
+    fn test_observer() {
+        let forest = example();
+
+        // Standing on the 9 at (4, 3), the 4 next to it is in sight and so is the 5 behind,
+        // which is taller than the 4, but not the 3 behind them both
+        assert!(visible_from(&forest, (4, 3), (3, 3), HeightRule::Shorter));
+        assert!(visible_from(&forest, (4, 3), (2, 3), HeightRule::Shorter));
+        assert!(!visible_from(&forest, (4, 3), (1, 3), HeightRule::Shorter));
+        assert!(visible_from(&forest, (4, 3), (4, 3), HeightRule::Shorter));
+
+        // Looking straight down column 2 from far above the forest
+        let from_above = visibility_map_from(&forest, (2, -100), HeightRule::Shorter);
+        assert!(from_above[(2, 0)]);
+        assert!(from_above[(2, 1)]);
+        assert!(!from_above[(2, 2)]);
+    }
+
+    #[test]
+    fn test_height_rules() {
+        let lines = Vec::from(["5151".to_owned()]);
+        let forest = Forest::from_input(&lines).unwrap();
+
+        assert!(!visible_from(&forest, (-1, 0), (2, 0), HeightRule::Shorter));
+        assert!(visible_from(
+            &forest,
+            (-1, 0),
+            (2, 0),
+            HeightRule::NotTaller
+        ));
+        assert!(!visible_from(
+            &forest,
+            (-1, 0),
+            (3, 0),
+            HeightRule::NotTaller
+        ));
+        assert!(visible_from(&forest, (1, 0), (2, 0), HeightRule::Shorter));
+
+        // With the eye up at 9 the line to the top of the last 5 passes over the others, but
+        // from down at 3 it runs into them
+        let lines = Vec::from(["3555".to_owned()]);
+        let forest = Forest::from_input(&lines).unwrap();
+        let high = HeightRule::Sightline { eye_height: 9.0 };
+        let low = HeightRule::Sightline { eye_height: 3.0 };
+        assert!(visible_from(&forest, (0, 0), (3, 0), high));
+        assert!(!visible_from(&forest, (0, 0), (3, 0), low));
+    }
+
+    #[test_case("shorter", Some(HeightRule::Shorter))]
+    #[test_case("not-taller", Some(HeightRule::NotTaller))]
+    #[test_case("sightline=1.5", Some(HeightRule::Sightline { eye_height: 1.5 }))]
+    #[test_case("sightline=x", None)]
+    #[test_case("taller", None)]
+    fn test_parse_rule(text: &str, expected: Option<HeightRule>) {
+        assert_eq!(parse_rule(text), expected);
+    }
+}
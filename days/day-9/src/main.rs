@@ -1,131 +1,198 @@
 use std::{
-    borrow::BorrowMut,
-    collections::HashSet,
+    env, fs,
     io::{self, BufRead},
+    path::Path,
+    process, thread,
+    time::Duration,
 };
 
-struct State {
-    positions: Vec<Position>,
+use render::{render_frame, render_visited, Bounds};
+use rope::{Instruction, Motion, Position, Rope, Track};
+
+mod render;
+mod rope;
+
+fn parse_instructions(lines: &[String]) -> Vec<Motion> {
+    let mut motions = Vec::new();
+    for line in lines {
+        let line_parts: Vec<&str> = line.split(' ').collect();
+        let count: u32 = line_parts[1].parse().unwrap();
+        let instruction = match line_parts[0] {
+            "U" => Instruction::Up,
+            "D" => Instruction::Down,
+            "L" => Instruction::Left,
+            "R" => Instruction::Right,
+            "UL" => Instruction::UpLeft,
+            "UR" => Instruction::UpRight,
+            "DL" => Instruction::DownLeft,
+            "DR" => Instruction::DownRight,
+            _ => panic!(),
+        };
+        motions.push(Motion { instruction, count });
+    }
+    motions
 }
 
-impl State {
-    fn new(num_positions: usize) -> Self {
-        let mut positions = Vec::with_capacity(num_positions);
+struct Options {
+    knots: Option<usize>,
+    track: Option<Track>,
+    animate: Option<u64>,
+    frames: Option<String>,
+    visited_map: Option<String>,
+}
+
+impl Options {
+    fn from_args(mut args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            knots: None,
+            track: None,
+            animate: None,
+            frames: None,
+            visited_map: None,
+        };
 
-        (0..num_positions).for_each(|i| positions.insert(i, Position { x: 0, y: 0 }));
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("{} requires a value", arg));
+            match arg.as_str() {
+                "--knots" => options.knots = Some(value.parse().unwrap()),
+                // A knot index counting the head as 0, `tail`, `all` or `none`
+                "--track" => {
+                    options.track = Some(match value.as_str() {
+                        "all" => Track::All,
+                        "none" => Track::None,
+                        "tail" => Track::Tail,
+                        index => Track::Knot(index.parse().unwrap()),
+                    })
+                }
+                // Milliseconds to show each step for
+                "--animate" => options.animate = Some(value.parse().unwrap()),
+                // A directory to write every step to as a numbered text file
+                "--frames" => options.frames = Some(value),
+                // A file to write the map of the cells the tail visits to
+                "--visited-map" => options.visited_map = Some(value),
+                _ => panic!("Unknown option: {}", arg),
+            }
+        }
 
-        Self { positions }
+        options
     }
 
-    fn tail(&self) -> Position {
-        *self.positions.last().unwrap()
+    fn visualise(&self) -> bool {
+        self.animate.is_some() || self.frames.is_some() || self.visited_map.is_some()
     }
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Copy)]
-struct Position {
-    x: i32,
-    y: i32,
-}
+fn simulate(motions: &[Motion], knots: usize, track: Track) -> Rope {
+    let mut rope = Rope::new(knots, track);
+    for motion in motions {
+        rope.apply(motion);
+    }
 
-enum Instruction {
-    Up,
-    Down,
-    Left,
-    Right,
+    rope
 }
 
-const UNIT_HYPOTENUSE: f32 = 1.41421356237;
+/// Draws the rope after every step, to the terminal and as frame files as the options ask,
+/// and then the map of the cells its tail visited. Every drawing covers the same box, which
+/// only needs to fit the head, since each knot stays inside the box around the one ahead
+fn visualise(motions: &[Motion], knots: usize, options: &Options) -> io::Result<()> {
+    let head = simulate(motions, knots, Track::Knot(0));
+    let bounds = Bounds::around(head.visited(0).unwrap());
+    let tail = knots - 1;
 
-fn parse_instructions(lines: &[String]) -> Vec<Instruction> {
-    let mut instructions = Vec::new();
-    for line in lines {
-        let line_parts: Vec<&str> = line.split(' ').collect();
-        let count: u32 = line_parts[1].parse().unwrap();
-        for _ in 0..count {
-            instructions.push(match line_parts[0] {
-                "U" => Instruction::Up,
-                "D" => Instruction::Down,
-                "L" => Instruction::Left,
-                "R" => Instruction::Right,
-                _ => panic!(),
-            })
+    let rope = if options.animate.is_some() || options.frames.is_some() {
+        let total = motions
+            .iter()
+            .map(|motion| motion.count as usize)
+            .sum::<usize>();
+        let width = total.to_string().len();
+        if let Some(dir) = &options.frames {
+            fs::create_dir_all(dir)?;
         }
-    }
-    instructions
-}
 
-fn execute_instruction(state: &mut State, instruction: &Instruction) {
-    let head_pos = state.positions.first_mut().unwrap();
+        let show = |step: usize, rope: &Rope| -> io::Result<()> {
+            let frame = render_frame(rope.knots(), rope.visited(tail), &bounds);
+            if let Some(delay) = options.animate {
+                // Clear the screen and draw from the top left corner
+                println!("\x1b[2J\x1b[H{}Step {} of {}", frame, step, total);
+                thread::sleep(Duration::from_millis(delay));
+            }
+            if let Some(dir) = &options.frames {
+                let name = format!("{:0width$}.txt", step, width = width);
+                fs::write(Path::new(dir).join(name), frame)?;
+            }
+            Ok(())
+        };
 
-    match instruction {
-        Instruction::Up => {
-            head_pos.y += 1;
-        }
-        Instruction::Down => {
-            head_pos.y -= 1;
+        let mut rope = Rope::new(knots, Track::Tail);
+        show(0, &rope)?;
+        for (step, instruction) in motions.iter().flat_map(Motion::steps).enumerate() {
+            rope.step(&instruction);
+            show(step + 1, &rope)?;
         }
-        Instruction::Left => {
-            head_pos.x -= 1;
-        }
-        Instruction::Right => {
-            head_pos.x += 1;
-        }
-    }
+        rope
+    } else {
+        simulate(motions, knots, Track::Tail)
+    };
 
-    let mut prev_pos = *head_pos;
-
-    for position in &mut state.positions[1..] {
-        move_if_needed(&prev_pos, position);
-        prev_pos = *position;
+    if let Some(path) = &options.visited_map {
+        fs::write(path, render_visited(rope.visited(tail).unwrap(), &bounds))?;
     }
-}
 
-fn move_if_needed(prev_pos: &Position, curr_pos: &mut Position) {
-    let distance = get_distance(prev_pos, curr_pos);
-    if distance.x.abs() == 2 && distance.y == 0 {
-        curr_pos.x += if distance.x > 0 { 1 } else { -1 };
-    } else if distance.y.abs() == 2 && distance.x == 0 {
-        curr_pos.y += if distance.y > 0 { 1 } else { -1 };
-    } else if (distance.y.abs() == 2 && [1, 2].contains(&distance.x.abs()))
-        || (distance.x.abs() == 2 && [1, 2].contains(&distance.y.abs()))
-    {
-        curr_pos.x += if distance.x > 0 { 1 } else { -1 };
-        curr_pos.y += if distance.y > 0 { 1 } else { -1 };
-    }
-}
-
-fn get_distance(pos_1: &Position, pos_2: &Position) -> Position {
-    Position {
-        x: pos_1.x - pos_2.x,
-        y: pos_1.y - pos_2.y,
-    }
+    Ok(())
 }
 
 fn main() {
-    let mut tail_positions = HashSet::new();
-    let mut tail_positions_2 = HashSet::new();
-    let mut state = State::new(2);
-    let mut state_2 = State::new(10);
+    let options = Options::from_args(env::args().skip(1));
     let lines: Vec<_> = io::stdin().lock().lines().map(|ln| ln.unwrap()).collect();
-    let instructions = parse_instructions(&lines);
+    let motions = parse_instructions(&lines);
+
+    // `day-9 --knots 2 --animate 100 --visited-map tail.txt` plays the rope back in the
+    // terminal a tenth of a second a step, then writes out where the tail went
+    if options.visualise() {
+        if let Err(error) = visualise(&motions, options.knots.unwrap_or(10), &options) {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+        return;
+    }
 
-    for instruction in &instructions {
-        execute_instruction(&mut state, instruction);
-        execute_instruction(&mut state_2, instruction);
-        tail_positions.insert(state.tail());
-        tail_positions_2.insert(state_2.tail());
+    // `day-9 --knots 5 --track all` reports how many positions every knot of a 5 knot rope
+    // visits, rather than solving the puzzle
+    if options.knots.is_some() || options.track.is_some() {
+        let knots = options.knots.unwrap_or(10);
+        let track = options.track.unwrap_or(Track::Tail);
+
+        let rope = simulate(&motions, knots, track);
+        if track == Track::None {
+            for (knot, Position { x, y }) in rope.knots().iter().enumerate() {
+                println!("Knot {}: ends at ({}, {})", knot, x, y);
+            }
+        }
+        for (knot, count) in rope.visited_counts() {
+            let Position { x, y } = rope.knots()[knot];
+            println!("Knot {}: {} positions, ends at ({}, {})", knot, count, x, y);
+        }
+        return;
     }
 
-    println!("Part 1: {}", tail_positions.len());
-    println!("Part 2: {}", tail_positions_2.len());
+    let rope = simulate(&motions, 2, Track::Tail);
+    let rope_2 = simulate(&motions, 10, Track::Tail);
+
+    println!("Part 1: {}", rope.visited(1).unwrap().len());
+    println!("Part 2: {}", rope_2.visited(9).unwrap().len());
 }
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashSet;
+    use std::{collections::HashSet, time::Instant};
 
-    use crate::{execute_instruction, parse_instructions, State};
+    use crate::{
+        parse_instructions,
+        rope::{Instruction, Motion, Rope, Track},
+        simulate,
+    };
 
     #[test]
     fn test_all() {
@@ -139,16 +206,17 @@ mod test {
             "L 5".to_owned(),
             "R 2".to_owned(),
         ];
-        let mut state = State::new(2);
+        let mut rope = Rope::new(2, Track::Knot(1));
         let mut tail_positions = HashSet::new();
-        let instructions = parse_instructions(&lines);
+        let motions = parse_instructions(&lines);
 
-        for instruction in instructions {
-            execute_instruction(&mut state, &instruction);
-            tail_positions.insert(state.tail());
+        for instruction in motions.iter().flat_map(|motion| motion.steps()) {
+            rope.step(&instruction);
+            tail_positions.insert(rope.knots()[1]);
         }
 
         assert_eq!(tail_positions.len(), 13);
+        assert_eq!(rope.visited(1), Some(&tail_positions));
     }
 
     #[test]
@@ -163,15 +231,59 @@ mod test {
             "L 25".to_owned(),
             "U 20".to_owned(),
         ];
-        let mut state = State::new(10);
-        let mut tail_positions = HashSet::new();
-        let instructions = parse_instructions(&lines);
+        let motions = parse_instructions(&lines);
+
+        let rope = simulate(&motions, 10, Track::Knot(9));
+
+        assert_eq!(rope.visited(9).unwrap().len(), 36);
+    }
+
+    #[test]
+    fn test_parse_keeps_counts() {
+        let lines = ["R 4000000000".to_owned(), "DL 2".to_owned()];
+
+        let motions = parse_instructions(&lines);
+
+        assert_eq!(
+            motions,
+            vec![
+                Motion {
+                    instruction: Instruction::Right,
+                    count: 4_000_000_000
+                },
+                Motion {
+                    instruction: Instruction::DownLeft,
+                    count: 2
+                }
+            ]
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn bench_long_motions() {
+        // A square spiral growing by ten million steps a side, followed without recording
+        // where the knots go
+        let lines = (1..=8)
+            .map(|side| {
+                let direction = ["R", "U", "L", "D"][side % 4];
+                format!("{} {}", direction, side * 10_000_000)
+            })
+            .collect::<Vec<_>>();
+        let motions = parse_instructions(&lines);
+
+        let start = Instant::now();
+        let sliding = simulate(&motions, 2, Track::None);
+        let sliding_time = start.elapsed();
 
-        for instruction in instructions {
-            execute_instruction(&mut state, &instruction);
-            tail_positions.insert(state.tail());
+        let start = Instant::now();
+        let mut stepping = Rope::new(2, Track::None);
+        for instruction in motions.iter().flat_map(|motion| motion.steps()) {
+            stepping.step(&instruction);
         }
+        let stepping_time = start.elapsed();
 
-        assert_eq!(tail_positions.len(), 36);
+        println!("sliding {:?}, stepping {:?}", sliding_time, stepping_time);
+        assert_eq!(sliding.knots(), stepping.knots());
     }
 }
@@ -0,0 +1,146 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::rope::Position;
+
+/// The part of the plane a drawing covers, from its bottom left to its top right corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    min: Position,
+    max: Position,
+}
+
+impl Bounds {
+    /// The smallest box around the start and every one of `positions`
+    pub fn around<'a>(positions: impl IntoIterator<Item = &'a Position>) -> Self {
+        let start = Position { x: 0, y: 0 };
+        let mut bounds = Self {
+            min: start,
+            max: start,
+        };
+        for position in positions {
+            bounds.min.x = bounds.min.x.min(position.x);
+            bounds.min.y = bounds.min.y.min(position.y);
+            bounds.max.x = bounds.max.x.max(position.x);
+            bounds.max.y = bounds.max.y.max(position.y);
+        }
+
+        bounds
+    }
+}
+
+/// How the puzzle marks a knot: `H` for the head, then the knots behind it numbered from 1.
+/// The tail of a two knot rope is `T`, as is any knot past 9
+fn label(knot: usize, knots: usize) -> char {
+    match knot {
+        0 => 'H',
+        1 if knots == 2 => 'T',
+        1..=9 => char::from_digit(knot as u32, 10).unwrap(),
+        _ => 'T',
+    }
+}
+
+/// Draws the rope like the puzzle, with `y` growing upwards. A knot covers the knots behind
+/// it, any knot covers the start `s` and the start covers the `#` of a visited cell
+pub fn render_frame(
+    knots: &[Position],
+    visited: Option<&HashSet<Position>>,
+    bounds: &Bounds,
+) -> String {
+    let start = Position { x: 0, y: 0 };
+    let mut out = String::new();
+    for y in (bounds.min.y..=bounds.max.y).rev() {
+        for x in bounds.min.x..=bounds.max.x {
+            let position = Position { x, y };
+            let cell = match knots.iter().position(|knot| *knot == position) {
+                Some(knot) => label(knot, knots.len()),
+                None if position == start => 's',
+                None if visited.is_some_and(|visited| visited.contains(&position)) => '#',
+                None => '.',
+            };
+            out.push(cell);
+        }
+        writeln!(out).unwrap();
+    }
+
+    out
+}
+
+/// Draws just the start and the visited cells, like the puzzle's map of the tail's path
+pub fn render_visited(visited: &HashSet<Position>, bounds: &Bounds) -> String {
+    render_frame(&[], Some(visited), bounds)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        parse_instructions,
+        render::{render_frame, render_visited, Bounds},
+        rope::{Position, Rope, Track},
+        simulate,
+    };
+
+    fn example() -> Vec<String> {
+        Vec::from([
+            "R 4".to_owned(),
+            "U 4".to_owned(),
+            "L 3".to_owned(),
+            "D 1".to_owned(),
+            "R 4".to_owned(),
+            "D 1".to_owned(),
+            "L 5".to_owned(),
+            "R 2".to_owned(),
+        ])
+    }
+
+    fn example_bounds() -> Bounds {
+        let rope = simulate(&parse_instructions(&example()), 2, Track::All);
+        Bounds::around(rope.visited(0).unwrap())
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(
+            example_bounds(),
+            Bounds {
+                min: Position { x: 0, y: 0 },
+                max: Position { x: 5, y: 4 }
+            }
+        );
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let motions = parse_instructions(&example()[..1]);
+        let bounds = example_bounds();
+
+        let rope = Rope::new(2, Track::Tail);
+        assert_eq!(
+            render_frame(rope.knots(), rope.visited(1), &bounds),
+            "......\n......\n......\n......\nH.....\n"
+        );
+
+        let rope = simulate(&motions, 2, Track::Tail);
+        assert_eq!(
+            render_frame(rope.knots(), rope.visited(1), &bounds),
+            "......\n......\n......\n......\ns##TH.\n"
+        );
+
+        // The 4 covers knots 5 to 9 and the start
+        let rope = simulate(&motions, 10, Track::Tail);
+        assert_eq!(
+            render_frame(rope.knots(), rope.visited(9), &bounds),
+            "......\n......\n......\n......\n4321H.\n"
+        );
+    }
+
+    #[test]
+    fn test_render_visited() {
+        let rope = simulate(&parse_instructions(&example()), 2, Track::Tail);
+
+        let expected = ["..##..", "...##.", ".####.", "....#.", "s###..", ""];
+        assert_eq!(
+            render_visited(rope.visited(1).unwrap(), &example_bounds()),
+            expected.join("\n")
+        );
+    }
+}
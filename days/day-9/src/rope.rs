@@ -0,0 +1,369 @@
+use std::{collections::HashSet, iter};
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Position {
+    pub x: i64,
+    pub y: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Instruction {
+    /// The change in `(x, y)` for one step, with `y` growing upwards
+    pub fn offset(&self) -> (i64, i64) {
+        match self {
+            Instruction::Up => (0, 1),
+            Instruction::Down => (0, -1),
+            Instruction::Left => (-1, 0),
+            Instruction::Right => (1, 0),
+            Instruction::UpLeft => (-1, 1),
+            Instruction::UpRight => (1, 1),
+            Instruction::DownLeft => (-1, -1),
+            Instruction::DownRight => (1, -1),
+        }
+    }
+}
+
+/// One line of the input, the head moving `count` steps in the same direction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Motion {
+    pub instruction: Instruction,
+    pub count: u32,
+}
+
+impl Motion {
+    pub fn steps(&self) -> impl Iterator<Item = Instruction> {
+        iter::repeat_n(self.instruction, self.count as usize)
+    }
+}
+
+/// Which knots have the positions they visit recorded, counting the head as knot 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Track {
+    Knot(usize),
+    Tail,
+    All,
+    /// Only follow where the knots are, which keeps very long motions cheap
+    None,
+}
+
+pub struct Rope {
+    knots: Vec<Position>,
+    // Positions visited by each knot, for the knots being tracked
+    visited: Vec<Option<HashSet<Position>>>,
+}
+
+impl Rope {
+    /// A rope with every knot at the origin, which counts as visited
+    pub fn new(knots: usize, track: Track) -> Self {
+        assert!(knots > 0, "a rope needs at least one knot");
+        if let Track::Knot(knot) = track {
+            assert!(knot < knots, "a {} knot rope has no knot {}", knots, knot);
+        }
+        let start = Position { x: 0, y: 0 };
+        let tracked = |knot| match track {
+            Track::Knot(tracked) => knot == tracked,
+            Track::Tail => knot == knots - 1,
+            Track::All => true,
+            Track::None => false,
+        };
+        let visited = (0..knots)
+            .map(|knot| tracked(knot).then(|| HashSet::from([start])))
+            .collect();
+
+        Self {
+            knots: vec![start; knots],
+            visited,
+        }
+    }
+
+    pub fn knots(&self) -> &[Position] {
+        &self.knots
+    }
+
+    /// Moves the head one step and lets every knot behind it follow
+    pub fn step(&mut self, instruction: &Instruction) {
+        let (dx, dy) = instruction.offset();
+        self.knots[0].x += dx;
+        self.knots[0].y += dy;
+
+        for knot in 1..self.knots.len() {
+            let previous = self.knots[knot - 1];
+            move_if_needed(&previous, &mut self.knots[knot]);
+        }
+
+        for (knot, visited) in self.knots.iter().zip(&mut self.visited) {
+            if let Some(visited) = visited {
+                visited.insert(*knot);
+            }
+        }
+    }
+
+    /// Runs a whole motion. A two knot rope that records nothing moves straight in one go,
+    /// anything else a step at a time, since recording every cell passed costs the same
+    /// either way
+    pub fn apply(&mut self, motion: &Motion) {
+        let (dx, dy) = motion.instruction.offset();
+        let recording = self.visited.iter().any(Option::is_some);
+        if self.knots.len() == 2 && (dx == 0 || dy == 0) && !recording {
+            self.slide((dx, dy), i64::from(motion.count));
+        } else {
+            for instruction in motion.steps() {
+                self.step(&instruction);
+            }
+        }
+    }
+
+    // Once the tail has to move its first step lands it on the head's line, and from then on
+    // it stays directly behind the head, so it ends one step short of the head
+    fn slide(&mut self, (dx, dy): (i64, i64), count: i64) {
+        let head = self.knots[0];
+        let tail = self.knots[1];
+        let along = |steps: i64| Position {
+            x: head.x + dx * steps,
+            y: head.y + dy * steps,
+        };
+        let end = along(count);
+        // How far ahead of the tail the head ends up, in the direction of the motion
+        let lead = (end.x - tail.x) * dx + (end.y - tail.y) * dy;
+
+        if lead > 1 {
+            self.knots[1] = along(count - 1);
+        }
+        self.knots[0] = end;
+    }
+
+    /// The positions a knot has visited, if it is tracked
+    pub fn visited(&self, knot: usize) -> Option<&HashSet<Position>> {
+        self.visited.get(knot)?.as_ref()
+    }
+
+    /// Every tracked knot with the number of positions it has visited
+    pub fn visited_counts(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.visited
+            .iter()
+            .enumerate()
+            .filter_map(|(knot, visited)| Some((knot, visited.as_ref()?.len())))
+    }
+}
+
+/// A knot stays put while it touches the knot ahead, diagonals included, so within a
+/// Chebyshev distance of 1. Otherwise it takes one step straight or diagonally towards it
+fn move_if_needed(prev_pos: &Position, curr_pos: &mut Position) {
+    let dx = prev_pos.x - curr_pos.x;
+    let dy = prev_pos.y - curr_pos.y;
+    if dx.abs().max(dy.abs()) > 1 {
+        curr_pos.x += dx.signum();
+        curr_pos.y += dy.signum();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::iter;
+
+    use crate::{
+        parse_instructions,
+        rope::{move_if_needed, Instruction, Motion, Position, Rope, Track},
+    };
+
+    // The original rule, listing the cases where the knot ahead has pulled away
+    fn move_if_needed_by_cases(prev_pos: &Position, curr_pos: &mut Position) {
+        let distance = Position {
+            x: prev_pos.x - curr_pos.x,
+            y: prev_pos.y - curr_pos.y,
+        };
+        if distance.x.abs() == 2 && distance.y == 0 {
+            curr_pos.x += if distance.x > 0 { 1 } else { -1 };
+        } else if distance.y.abs() == 2 && distance.x == 0 {
+            curr_pos.y += if distance.y > 0 { 1 } else { -1 };
+        } else if (distance.y.abs() == 2 && [1, 2].contains(&distance.x.abs()))
+            || (distance.x.abs() == 2 && [1, 2].contains(&distance.y.abs()))
+        {
+            curr_pos.x += if distance.x > 0 { 1 } else { -1 };
+            curr_pos.y += if distance.y > 0 { 1 } else { -1 };
+        }
+    }
+
+    #[test]
+    fn test_follow_matches_cases() {
+        // A knot can never be further than 2 away on either axis from the one ahead, so this
+        // covers every position it can be left in
+        let leader = Position { x: 0, y: 0 };
+        for x in -2..=2 {
+            for y in -2..=2 {
+                let mut by_signum = Position { x, y };
+                let mut by_cases = Position { x, y };
+
+                move_if_needed(&leader, &mut by_signum);
+                move_if_needed_by_cases(&leader, &mut by_cases);
+
+                assert_eq!(by_signum, by_cases, "knot at ({}, {})", x, y);
+                assert!(by_signum.x.abs() <= 1 && by_signum.y.abs() <= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_diagonal_head_moves() {
+        let lines = ["UR 2".to_owned(), "DL 1".to_owned(), "R 2".to_owned()];
+        let mut rope = Rope::new(3, Track::All);
+
+        for motion in parse_instructions(&lines) {
+            rope.apply(&motion);
+        }
+
+        assert_eq!(
+            rope.knots(),
+            &[
+                Position { x: 3, y: 1 },
+                Position { x: 2, y: 1 },
+                Position { x: 1, y: 1 }
+            ]
+        );
+        assert_eq!(
+            rope.visited_counts().collect::<Vec<_>>(),
+            vec![(0, 5), (1, 3), (2, 2)]
+        );
+    }
+
+    // Motions in all eight directions of up to `max_count` steps, from a linear congruential
+    // generator
+    fn random_motions(mut seed: u64, max_count: u32) -> impl Iterator<Item = Motion> {
+        iter::repeat_with(move || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let instruction = match seed >> 61 {
+                0 => Instruction::Up,
+                1 => Instruction::Down,
+                2 => Instruction::Left,
+                3 => Instruction::Right,
+                4 => Instruction::UpLeft,
+                5 => Instruction::UpRight,
+                6 => Instruction::DownLeft,
+                _ => Instruction::DownRight,
+            };
+            Motion {
+                instruction,
+                count: (seed >> 32) as u32 % (max_count + 1),
+            }
+        })
+    }
+
+    #[test]
+    fn test_knots_stay_touching() {
+        let mut rope = Rope::new(10, Track::Tail);
+        for motion in random_motions(11, 3).take(5_000) {
+            for instruction in motion.steps() {
+                rope.step(&instruction);
+
+                for pair in rope.knots().windows(2) {
+                    let (dx, dy) = (pair[0].x - pair[1].x, pair[0].y - pair[1].y);
+                    assert!(dx.abs() <= 1 && dy.abs() <= 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_slide_matches_steps() {
+        let mut sliding = Rope::new(2, Track::None);
+        let mut stepping = Rope::new(2, Track::None);
+        for motion in random_motions(3, 5).take(1_000) {
+            sliding.apply(&motion);
+            for instruction in motion.steps() {
+                stepping.step(&instruction);
+            }
+
+            assert_eq!(sliding.knots(), stepping.knots(), "after {:?}", motion);
+        }
+    }
+
+    #[test]
+    fn test_slide_past_i32() {
+        // Nothing is recorded, which saves storing billions of positions
+        let mut rope = Rope::new(2, Track::None);
+
+        rope.apply(&Motion {
+            instruction: Instruction::Right,
+            count: 3_000_000_000,
+        });
+        rope.apply(&Motion {
+            instruction: Instruction::Down,
+            count: u32::MAX,
+        });
+
+        assert_eq!(
+            rope.knots(),
+            &[
+                Position {
+                    x: 3_000_000_000,
+                    y: -4_294_967_295
+                },
+                Position {
+                    x: 3_000_000_000,
+                    y: -4_294_967_294
+                }
+            ]
+        );
+        assert_eq!(rope.visited_counts().count(), 0);
+    }
+
+    #[test]
+    fn test_track_all_knots() {
+        let lines = ["R 4".to_owned(), "U 4".to_owned()];
+        let mut rope = Rope::new(3, Track::All);
+
+        for motion in parse_instructions(&lines) {
+            rope.apply(&motion);
+        }
+
+        assert_eq!(
+            rope.knots(),
+            &[
+                Position { x: 4, y: 4 },
+                Position { x: 4, y: 3 },
+                Position { x: 4, y: 2 }
+            ]
+        );
+        assert_eq!(
+            rope.visited_counts().collect::<Vec<_>>(),
+            vec![(0, 9), (1, 7), (2, 5)]
+        );
+        assert!(rope.visited(1).unwrap().contains(&Position { x: 3, y: 0 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "a 3 knot rope has no knot 3")]
+    fn test_track_missing_knot() {
+        Rope::new(3, Track::Knot(3));
+    }
+
+    #[test]
+    fn test_track_one_knot() {
+        let lines = ["R 4".to_owned(), "U 4".to_owned()];
+        let mut rope = Rope::new(3, Track::Knot(1));
+        let mut tail = Rope::new(3, Track::Tail);
+
+        for motion in parse_instructions(&lines) {
+            rope.apply(&motion);
+            tail.apply(&motion);
+        }
+
+        assert_eq!(rope.visited(0), None);
+        assert_eq!(rope.visited(2), None);
+        assert_eq!(rope.visited_counts().collect::<Vec<_>>(), vec![(1, 7)]);
+        assert_eq!(tail.visited_counts().collect::<Vec<_>>(), vec![(2, 5)]);
+    }
+}
@@ -0,0 +1,415 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The change in `(x, y)` for one step, with `y` growing down the grid
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GridError {
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    InvalidCell {
+        row: usize,
+        column: usize,
+        found: char,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells, expected {} like the first row",
+                row, found, expected
+            ),
+            GridError::InvalidCell { row, column, found } => {
+                write!(f, "row {}, column {}: unexpected '{}'", row, column, found)
+            }
+        }
+    }
+}
+
+/// A rectangular grid stored row by row. Positions are `(x, y)` with `(0, 0)` at the top
+/// left, so `x` picks the column and `y` the row
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    columns: usize,
+    rows: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(columns: usize, rows: usize, cells: Vec<T>) -> Self {
+        assert_eq!(
+            cells.len(),
+            columns * rows,
+            "{}x{} grid needs {} cells",
+            columns,
+            rows,
+            columns * rows
+        );
+        Self {
+            cells,
+            columns,
+            rows,
+        }
+    }
+
+    pub fn from_fn(columns: usize, rows: usize, mut cell: impl FnMut(usize, usize) -> T) -> Self {
+        let cells = (0..rows)
+            .flat_map(|y| (0..columns).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+        Self::new(columns, rows, cells)
+    }
+
+    /// Reads one cell per character, where `cell` returns `None` for a character that is not
+    /// allowed. Rows and columns in errors count from 1
+    pub fn parse(
+        lines: &[impl AsRef<str>],
+        mut cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, GridError> {
+        let rows = lines.len();
+        let columns = lines
+            .first()
+            .map_or(0, |line| line.as_ref().chars().count());
+        let mut cells = Vec::with_capacity(rows * columns);
+        for (row, line) in lines.iter().enumerate() {
+            let line = line.as_ref();
+            let found = line.chars().count();
+            if found != columns {
+                return Err(GridError::RaggedRow {
+                    row: row + 1,
+                    expected: columns,
+                    found,
+                });
+            }
+            for (column, c) in line.chars().enumerate() {
+                cells.push(cell(c).ok_or(GridError::InvalidCell {
+                    row: row + 1,
+                    column: column + 1,
+                    found: c,
+                })?);
+            }
+        }
+
+        Ok(Self::new(columns, rows, cells))
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Every cell in reading order
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        self.index_of(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        self.index_of(x, y).map(|index| &mut self.cells[index])
+    }
+
+    fn index_of(&self, x: usize, y: usize) -> Option<usize> {
+        (x < self.columns && y < self.rows).then_some(x + y * self.columns)
+    }
+
+    /// The position one step away, if it is still on the grid
+    pub fn step(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let (dx, dy) = direction.offset();
+        let x = x.checked_add_signed(dx)?;
+        let y = y.checked_add_signed(dy)?;
+        self.index_of(x, y).map(|_| (x, y))
+    }
+
+    /// Every position in reading order
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let columns = self.columns;
+        (0..self.cells.len()).map(move |index| (index % columns, index / columns))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.columns..(y + 1) * self.columns]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.columns..(y + 1) * self.columns]
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(x < self.columns, "column {} is outside the grid", x);
+        self.cells.iter().skip(x).step_by(self.columns)
+    }
+
+    /// The cells from `(x, y)` to the edge of the grid in one direction, starting with the
+    /// cell at `(x, y)` itself
+    pub fn line(&self, x: usize, y: usize, direction: Direction) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            next: self.index_of(x, y).map(|_| (x, y)),
+            direction,
+        }
+    }
+
+    /// The cells seen looking out from `(x, y)` to the edge of the grid, nearest first
+    pub fn ray(&self, x: usize, y: usize, direction: Direction) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            next: self.step((x, y), direction),
+            direction,
+        }
+    }
+
+    /// The cells directly above, below, left and right of `(x, y)` that are on the grid
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = ((usize, usize), &T)> {
+        Direction::ALL.into_iter().filter_map(move |direction| {
+            let (x, y) = self.step((x, y), direction)?;
+            Some(((x, y), &self[(x, y)]))
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid::new(self.columns, self.rows, self.cells.iter().map(f).collect())
+    }
+}
+
+impl Grid<u32> {
+    pub fn from_digits(lines: &[impl AsRef<str>]) -> Result<Self, GridError> {
+        Self::parse(lines, |c| c.to_digit(10))
+    }
+}
+
+impl Grid<char> {
+    pub fn from_chars(lines: &[impl AsRef<str>]) -> Result<Self, GridError> {
+        Self::parse(lines, Some)
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        match self.index_of(x, y) {
+            Some(index) => &self.cells[index],
+            None => panic!("({}, {}) is outside the grid", x, y),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        match self.index_of(x, y) {
+            Some(index) => &mut self.cells[index],
+            None => panic!("({}, {}) is outside the grid", x, y),
+        }
+    }
+}
+
+/// The points on a straight line from `from` to `to`, both included, chosen with Bresenham's
+/// algorithm so consecutive points are always touching, diagonals included
+pub fn bresenham(from: (isize, isize), to: (isize, isize)) -> Bresenham {
+    Bresenham {
+        next: Some(from),
+        to,
+        delta: ((to.0 - from.0).abs(), -(to.1 - from.1).abs()),
+        step: ((to.0 - from.0).signum(), (to.1 - from.1).signum()),
+        error: (to.0 - from.0).abs() - (to.1 - from.1).abs(),
+    }
+}
+
+pub struct Bresenham {
+    next: Option<(isize, isize)>,
+    to: (isize, isize),
+    // The distance to cover along x and, negated, along y
+    delta: (isize, isize),
+    step: (isize, isize),
+    error: isize,
+}
+
+impl Iterator for Bresenham {
+    type Item = (isize, isize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (x, y) = self.next?;
+        if (x, y) == self.to {
+            self.next = None;
+            return Some((x, y));
+        }
+
+        let doubled = 2 * self.error;
+        let (mut next_x, mut next_y) = (x, y);
+        if doubled >= self.delta.1 {
+            self.error += self.delta.1;
+            next_x += self.step.0;
+        }
+        if doubled <= self.delta.0 {
+            self.error += self.delta.0;
+            next_y += self.step.1;
+        }
+        self.next = Some((next_x, next_y));
+
+        Some((x, y))
+    }
+}
+
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    next: Option<(usize, usize)>,
+    direction: Direction,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = ((usize, usize), &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let position = self.next?;
+        self.next = self.grid.step(position, self.direction);
+        Some((position, &self.grid[position]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use crate::{bresenham, Direction, Grid, GridError};
+
+    fn example() -> Grid<u32> {
+        Grid::from_digits(&["123", "456"]).unwrap()
+    }
+
+    #[test]
+    fn test_get() {
+        let grid = example();
+
+        assert_eq!(grid.columns(), 3);
+        assert_eq!(grid.rows(), 2);
+        assert_eq!(grid.get(2, 0), Some(&3));
+        assert_eq!(grid.get(0, 1), Some(&4));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(0, 2), None);
+        assert_eq!(grid[(1, 1)], 5);
+    }
+
+    #[test]
+    fn test_rows_and_columns() {
+        let grid = example();
+
+        assert_eq!(grid.row(1), &[4, 5, 6]);
+        assert_eq!(grid.column(2).collect::<Vec<_>>(), vec![&3, &6]);
+        assert_eq!(grid.column(0).rev().collect::<Vec<_>>(), vec![&4, &1]);
+    }
+
+    #[test_case(Direction::Up, &[])]
+    #[test_case(Direction::Down, &[5])]
+    #[test_case(Direction::Left, &[1])]
+    #[test_case(Direction::Right, &[3])]
+    fn test_ray(direction: Direction, expected: &[u32]) {
+        let cells = example()
+            .ray(1, 0, direction)
+            .map(|(_, cell)| *cell)
+            .collect::<Vec<_>>();
+
+        assert_eq!(cells, expected);
+    }
+
+    #[test]
+    fn test_line() {
+        let line = example()
+            .line(2, 1, Direction::Left)
+            .map(|(position, cell)| (position, *cell))
+            .collect::<Vec<_>>();
+
+        assert_eq!(line, vec![((2, 1), 6), ((1, 1), 5), ((0, 1), 4)]);
+    }
+
+    #[test_case((0, 0), (0, 0), &[(0, 0)])]
+    #[test_case((0, 0), (3, 0), &[(0, 0), (1, 0), (2, 0), (3, 0)])]
+    #[test_case((0, 0), (-2, -2), &[(0, 0), (-1, -1), (-2, -2)])]
+    #[test_case((0, 0), (4, 2), &[(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)])]
+    #[test_case((1, 5), (2, 1), &[(1, 5), (1, 4), (2, 3), (2, 2), (2, 1)])]
+    fn test_bresenham(from: (isize, isize), to: (isize, isize), expected: &[(isize, isize)]) {
+        assert_eq!(bresenham(from, to).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+
+        let neighbours = grid
+            .neighbours(0, 1)
+            .map(|(position, cell)| (position, *cell))
+            .collect::<Vec<_>>();
+
+        assert_eq!(neighbours, vec![((0, 0), 1), ((1, 1), 5)]);
+    }
+
+    #[test]
+    fn test_from_fn_and_map() {
+        let grid = Grid::from_fn(3, 2, |x, y| (x + y * 3 + 1) as u32);
+
+        assert_eq!(grid, example());
+        assert_eq!(
+            grid.map(|cell| cell % 2 == 0).cells(),
+            &[false, true, false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_from_chars() {
+        let grid = Grid::from_chars(&["#.", ".#"]).unwrap();
+
+        assert_eq!(grid.cells(), &['#', '.', '.', '#']);
+    }
+
+    #[test_case(&["123", "45"], GridError::RaggedRow { row: 2, expected: 3, found: 2 })]
+    #[test_case(&["12", "3x"], GridError::InvalidCell { row: 2, column: 2, found: 'x' })]
+    fn test_from_digits_errors(lines: &[&str], expected: GridError) {
+        assert_eq!(Grid::from_digits(lines).err(), Some(expected));
+    }
+}